@@ -0,0 +1,110 @@
+use anyhow::{bail, Context, Result};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Human names for offsets, persisted as `name = 0x1a4` lines.
+pub struct Aliases {
+    path: PathBuf,
+    offsets: BTreeMap<String, usize>,
+}
+
+impl Aliases {
+    /// Loads aliases from `path`; a missing file is an empty table. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut offsets = BTreeMap::new();
+        if path.exists() {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read aliases from {}", path.display()))?;
+            for (i, line) in text.lines().enumerate() {
+                if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                    continue;
+                }
+                let parsed = line.split_once('=').and_then(|(name, offset)| {
+                    Some((name.trim().to_string(), crate::parse_offset(offset.trim())?))
+                });
+                let Some((name, offset)) = parsed else {
+                    bail!("{}:{}: expected `name = offset`", path.display(), i + 1);
+                };
+                offsets.insert(name, offset);
+            }
+        }
+        Ok(Aliases { path, offsets })
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.offsets.get(name).copied()
+    }
+
+    /// Defines or redefines `name` and writes the table back to disk.
+    pub fn set(&mut self, name: &str, offset: usize) -> Result<()> {
+        if crate::parse_offset(name).is_some() {
+            bail!("alias name {name:?} would be read as an offset");
+        }
+        // These would make the written `name = 0x1a4` line unreadable or a comment on the next
+        // load.
+        if name.is_empty()
+            || name.starts_with('#')
+            || name.contains(|c: char| c == '=' || c.is_whitespace())
+        {
+            bail!("alias name {name:?} must not be empty, start with `#` or contain `=` or whitespace");
+        }
+        // The prompt handles these before it looks aliases up, so the alias could never be used.
+        if name.starts_with(['/', ':', '@', '+', '-']) || name.starts_with("offsetof(") {
+            bail!("alias name {name:?} must not start with `/`, `:`, `@`, `+`, `-` or `offsetof(`");
+        }
+        self.offsets.insert(name.to_string(), offset);
+        let text: String = self
+            .iter()
+            .map(|(name, offset)| format!("{name} = 0x{offset:x}\n"))
+            .collect();
+        std::fs::write(&self.path, text)
+            .with_context(|| format!("failed to write aliases to {}", self.path.display()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.offsets
+            .iter()
+            .map(|(name, offset)| (name.as_str(), *offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aliases;
+
+    #[test]
+    fn set_round_trips_through_load() {
+        let path = std::env::temp_dir().join(format!("sdk-lookup-aliases-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut aliases = Aliases::load(path.clone()).unwrap();
+        for (name, offset) in [("health", 0x1a4), ("m_hOwner", 0x440), ("a#b", 0x10)] {
+            aliases.set(name, offset).unwrap();
+        }
+        for name in [
+            "",
+            "#health",
+            "max health",
+            "a=b",
+            "/health",
+            ":health",
+            "@health",
+            "+health",
+            "-health",
+            "offsetof(x",
+            "1a4",
+            "0x10",
+        ] {
+            assert!(aliases.set(name, 0x1).is_err(), "{name:?}");
+        }
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("# comment\n\n");
+        std::fs::write(&path, text).unwrap();
+        let loaded = Aliases::load(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let loaded: Vec<_> = loaded.iter().collect();
+        assert_eq!(
+            loaded,
+            [("a#b", 0x10), ("health", 0x1a4), ("m_hOwner", 0x440)]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::Sdk;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Checks `Class::field = 0x1a4` assertions from `path` against `sdk`, printing a `FAIL` line for
+/// each one that no longer holds and returning the number of failures. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn check_expectations(sdk: &Sdk, path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read expectations from {}", path.display()))?;
+    let (mut checked, mut failures) = (0, 0);
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line.split_once('=').and_then(|(member, offset)| {
+            let (class_name, field_name) = member.trim().split_once("::")?;
+            Some((class_name, field_name, crate::parse_offset(offset.trim())?))
+        });
+        let Some((class_name, field_name, expected)) = parsed else {
+            bail!(
+                "{}:{}: expected `Class::field = offset`",
+                path.display(),
+                i + 1
+            );
+        };
+        checked += 1;
+        let mut found: Vec<usize> = sdk
+            .type_scopes
+            .values()
+            .filter_map(|type_scope| type_scope.classes.get(class_name))
+            .filter_map(|class| class.fields.get(field_name))
+            .map(|field| field.offset)
+            .collect();
+        if found.contains(&expected) {
+            continue;
+        }
+        failures += 1;
+        found.sort();
+        found.dedup();
+        let found = match &found[..] {
+            [] => "not found".to_string(),
+            offsets => {
+                let offsets: Vec<_> = offsets
+                    .iter()
+                    .map(|offset| format!("0x{offset:x}"))
+                    .collect();
+                format!("found {}", offsets.join(", "))
+            }
+        };
+        println!("FAIL {class_name}::{field_name}: expected 0x{expected:x}, {found}");
+    }
+    println!("{failures} of {checked} expectations failed");
+    Ok(failures)
+}
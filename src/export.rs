@@ -0,0 +1,60 @@
+use crate::{FieldEntry, Sdk};
+use anyhow::{Context, Result};
+use serde::{Serialize, Serializer};
+use std::{collections::HashMap, fs::File, io::BufWriter, path::Path};
+
+/// Serializes the index as an object keyed by hex offset, in ascending offset order.
+struct IndexDump<'a>(&'a HashMap<usize, Vec<FieldEntry>>);
+
+impl Serialize for IndexDump<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut offsets: Vec<_> = self.0.keys().copied().collect();
+        offsets.sort_unstable();
+        serializer.collect_map(
+            offsets
+                .into_iter()
+                .map(|offset| (format!("0x{offset:x}"), &self.0[&offset])),
+        )
+    }
+}
+
+/// Writes the whole offset index to `path` as pretty-printed JSON, so snapshots diff cleanly.
+pub fn dump_index(offset_to_fields: &HashMap<usize, Vec<FieldEntry>>, path: &Path) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &IndexDump(offset_to_fields))
+        .with_context(|| format!("failed to write index to {}", path.display()))
+}
+
+/// Writes every field without a type to `path`, listed under its scope and class after a count
+/// of such fields per scope.
+pub fn write_unresolved_report(sdk: &Sdk, path: &Path) -> Result<()> {
+    let mut type_scopes: Vec<_> = sdk.type_scopes.iter().collect();
+    type_scopes.sort_by_key(|(type_scope_name, _)| *type_scope_name);
+    let mut counts = String::new();
+    let mut listing = String::new();
+    for (type_scope_name, type_scope) in type_scopes {
+        let mut classes: Vec<_> = type_scope.classes.iter().collect();
+        classes.sort_by_key(|(class_name, _)| *class_name);
+        let mut count = 0;
+        for (class_name, class) in classes {
+            let mut fields: Vec<_> = class
+                .fields
+                .iter()
+                .filter(|(_, field)| field.type_.is_empty())
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            fields.sort_by(|a, b| (a.1.offset, a.0).cmp(&(b.1.offset, b.0)));
+            count += fields.len();
+            listing.push_str(&format!("\n{type_scope_name}!{class_name}\n"));
+            for (field_name, field) in fields {
+                listing.push_str(&format!("  0x{:x} {field_name}\n", field.offset));
+            }
+        }
+        counts.push_str(&format!("{type_scope_name}: {count} unresolved fields\n"));
+    }
+    std::fs::write(path, counts + &listing)
+        .with_context(|| format!("failed to write report to {}", path.display()))
+}
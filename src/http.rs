@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Hashes `url` with 64-bit FNV-1a. Unlike `DefaultHasher`, the result is fixed, so a cached
+/// copy is found again after the tool is rebuilt with a newer Rust.
+fn cache_key(url: &str) -> u64 {
+    url.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Downloads a schema document, reusing the cached copy when the server reports it unchanged
+/// through its ETag, or when the server can't be reached.
+pub fn fetch_schema(url: &str) -> Result<String> {
+    let cache_dir = std::env::temp_dir().join("sdk-lookup-cache");
+    let cache_file = cache_dir.join(format!("{:016x}.json", cache_key(url)));
+    let etag_file = cache_file.with_extension("etag");
+    let cached = || {
+        std::fs::read_to_string(&cache_file)
+            .with_context(|| format!("failed to read cached {}", cache_file.display()))
+    };
+
+    let mut request = ureq::get(url);
+    if let (Ok(etag), true) = (std::fs::read_to_string(&etag_file), cache_file.exists()) {
+        request = request.set("If-None-Match", etag.trim());
+    }
+    let response = match request.call() {
+        Ok(response) if response.status() == 304 => return cached(),
+        Ok(response) => response,
+        Err(e) if cache_file.exists() => {
+            eprintln!("{e}, using cached copy");
+            return cached();
+        }
+        Err(e) => return Err(e).with_context(|| format!("failed to fetch {url}")),
+    };
+    let etag = response.header("ETag").map(str::to_string);
+    let mut body = String::new();
+    response
+        .into_reader()
+        .read_to_string(&mut body)
+        .with_context(|| format!("failed to download {url}"))?;
+
+    std::fs::create_dir_all(&cache_dir)?;
+    std::fs::write(&cache_file, &body)?;
+    match etag {
+        Some(etag) => std::fs::write(&etag_file, etag)?,
+        None => {
+            let _ = std::fs::remove_file(&etag_file);
+        }
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cache_key;
+
+    #[test]
+    fn cache_key_is_fnv1a() {
+        assert_eq!(cache_key(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(cache_key("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,19 @@
+/// Infers the natural alignment of a schema type from its name, for the primitive, pointer and
+/// handle types whose layout is fixed. Returns `None` for everything else.
+pub fn natural_alignment(type_: &str) -> Option<usize> {
+    let type_ = type_.trim();
+    if type_.ends_with('*') {
+        return Some(8);
+    }
+    if type_.starts_with("CHandle<") {
+        return Some(4);
+    }
+    match type_ {
+        "bool" | "char" | "int8" | "uint8" => Some(1),
+        "int16" | "uint16" => Some(2),
+        "int32" | "uint32" | "float32" | "Vector" | "QAngle" | "Vector2D" | "Vector4D"
+        | "CUtlStringToken" | "GameTime_t" | "GameTick_t" => Some(4),
+        "int64" | "uint64" | "float64" | "CUtlString" | "CUtlSymbolLarge" => Some(8),
+        _ => None,
+    }
+}
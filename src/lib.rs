@@ -0,0 +1,336 @@
+//! Schema model and offset index behind `sdk-lookup`.
+//!
+//! An [`Sdk`] can be deserialized from any source, not just the files the command line tool
+//! reads, and [`make_offset_to_fields`] turns it into the index every lookup runs against.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A whole schema dump: type scopes (such as `client` or `server`) by name.
+#[derive(Debug, Deserialize)]
+pub struct Sdk {
+    #[serde(flatten)]
+    pub type_scopes: HashMap<String, TypeScope>,
+}
+
+impl Sdk {
+    /// Parses a schema document, ignoring the byte order mark some Windows tools prepend.
+    /// `source` names the document in error messages.
+    pub fn from_json(text: &str, source: &str) -> Result<Self> {
+        serde_json::from_str(text.strip_prefix('\u{feff}').unwrap_or(text)).map_err(|e| {
+            // serde_json appends the position to its message; report it up front instead.
+            let message = e.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(message, _)| message);
+            anyhow!(
+                "failed to parse {source} at line {} column {}: {message}",
+                e.line(),
+                e.column()
+            )
+        })
+    }
+
+    /// Layers `patch` over this schema class by class: a patched class replaces the base class
+    /// entirely, or with `merge_fields` only adds and replaces the fields the patch declares.
+    /// Returns the `(scope, class)` pairs that existed in both and were overridden, sorted.
+    pub fn merge(&mut self, patch: Sdk, merge_fields: bool) -> Vec<(String, String)> {
+        let mut overridden = Vec::new();
+        for (type_scope_name, type_scope) in patch.type_scopes {
+            let base = self
+                .type_scopes
+                .entry(type_scope_name.clone())
+                .or_insert_with(|| TypeScope {
+                    classes: HashMap::new(),
+                });
+            for (class_name, class) in type_scope.classes {
+                match base.classes.get_mut(&class_name) {
+                    Some(existing) => {
+                        if merge_fields {
+                            existing.fields.extend(class.fields);
+                        } else {
+                            *existing = class;
+                        }
+                        overridden.push((type_scope_name.clone(), class_name));
+                    }
+                    None => {
+                        base.classes.insert(class_name, class);
+                    }
+                }
+            }
+        }
+        overridden.sort();
+        overridden
+    }
+
+    pub fn has_class(&self, class_name: &str) -> bool {
+        self.type_scopes
+            .values()
+            .any(|type_scope| type_scope.classes.contains_key(class_name))
+    }
+
+    /// Returns the type of `class_name::field_name` from the first scope that declares it, or
+    /// `None` if it isn't declared or its type is unknown.
+    pub fn get_field_type(&self, class_name: &str, field_name: &str) -> Option<&str> {
+        self.type_scopes
+            .values()
+            .filter_map(|type_scope| type_scope.classes.get(class_name))
+            .find_map(|class| class.fields.get(field_name))
+            .map(|field| field.type_.as_str())
+            .filter(|type_| !type_.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TypeScope {
+    #[serde(flatten)]
+    pub classes: HashMap<String, Class>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct Class {
+    #[serde(flatten)]
+    pub fields: HashMap<String, Field>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct Field {
+    pub offset: usize,
+    /// Empty when the generator couldn't determine the type.
+    #[serde(default)]
+    pub type_: String,
+    /// Position within the byte at `offset` for bitfield members, if the dump records one.
+    #[serde(default)]
+    pub bit_offset: Option<u32>,
+}
+
+/// One field as listed in the offset index.
+#[derive(Debug, Serialize)]
+pub struct FieldEntry {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(rename = "class")]
+    pub class_name: String,
+    #[serde(rename = "scope")]
+    pub type_scope_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_offset: Option<u32>,
+}
+
+/// Builds the offset index of `sdk`: every field of every class, keyed by offset.
+///
+/// The index copies the names and types it needs, so it does not borrow from `sdk` and stays
+/// valid after the `Sdk` is dropped or replaced. Results that borrow from the index, such as
+/// search hits, live only as long as the index itself.
+pub fn make_offset_to_fields(sdk: &Sdk) -> HashMap<usize, Vec<FieldEntry>> {
+    let mut offset_to_fields = HashMap::new();
+    for (type_scope_name, type_scope) in &sdk.type_scopes {
+        for (class_name, class) in &type_scope.classes {
+            for (field_name, field) in &class.fields {
+                offset_to_fields
+                    .entry(field.offset)
+                    .or_insert_with(Vec::new)
+                    .push(FieldEntry {
+                        name: field_name.clone(),
+                        type_: field.type_.clone(),
+                        class_name: class_name.clone(),
+                        type_scope_name: type_scope_name.clone(),
+                        bit_offset: field.bit_offset,
+                    });
+            }
+        }
+    }
+    // Keep same-named fields next to each other so related entries read as one group. Bitfield
+    // members sharing the byte follow in bit order.
+    for fields in offset_to_fields.values_mut() {
+        fields.sort_by(|a, b| {
+            a.bit_offset
+                .cmp(&b.bit_offset)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.type_scope_name.cmp(&b.type_scope_name))
+                .then_with(|| a.class_name.cmp(&b.class_name))
+        });
+    }
+    offset_to_fields
+}
+
+/// Builds the offset index the command line tool looks offsets up in: [`make_offset_to_fields`],
+/// with each offset's fields reordered by [`sort_by_scope_order`] when `scope_order` isn't
+/// empty. Output filters such as `--only` are applied when printing, not here, so the index
+/// always lists every field.
+pub fn build_index(sdk: &Sdk, scope_order: &[String]) -> HashMap<usize, Vec<FieldEntry>> {
+    let mut offset_to_fields = make_offset_to_fields(sdk);
+    if !scope_order.is_empty() {
+        sort_by_scope_order(&mut offset_to_fields, scope_order);
+    }
+    offset_to_fields
+}
+
+/// Same-named fields at one offset whose types disagree, such as `m_iHealth` being `int32` in
+/// one class and `int64` in another.
+#[derive(Debug)]
+pub struct TypeConflict<'a> {
+    pub offset: usize,
+    pub name: &'a str,
+    pub fields: Vec<&'a FieldEntry>,
+}
+
+/// Finds every type conflict in the index, ordered by offset and name.
+pub fn find_type_conflicts(
+    offset_to_fields: &HashMap<usize, Vec<FieldEntry>>,
+) -> Vec<TypeConflict<'_>> {
+    let mut conflicts = Vec::new();
+    for (offset, fields) in offset_to_fields {
+        // Group by name explicitly: `sort_by_scope_order` may have separated same-named fields.
+        let mut by_name: BTreeMap<&str, Vec<&FieldEntry>> = BTreeMap::new();
+        for field in fields {
+            by_name.entry(&field.name).or_default().push(field);
+        }
+        for (name, group) in by_name {
+            if group.iter().any(|field| field.type_ != group[0].type_) {
+                conflicts.push(TypeConflict {
+                    offset: *offset,
+                    name,
+                    fields: group,
+                });
+            }
+        }
+    }
+    conflicts.sort_by(|a, b| (a.offset, a.name).cmp(&(b.offset, b.name)));
+    conflicts
+}
+
+/// Reorders every offset's fields so scopes listed in `scope_order` come first, in that order,
+/// followed by the remaining scopes alphabetically. Fields keep their name order within a scope,
+/// so a name declared in several scopes is no longer adjacent to itself.
+pub fn sort_by_scope_order(
+    offset_to_fields: &mut HashMap<usize, Vec<FieldEntry>>,
+    scope_order: &[String],
+) {
+    let rank = |field: &FieldEntry| {
+        scope_order
+            .iter()
+            .position(|scope| *scope == field.type_scope_name)
+            .unwrap_or(scope_order.len())
+    };
+    for fields in offset_to_fields.values_mut() {
+        fields.sort_by(|a, b| {
+            rank(a)
+                .cmp(&rank(b))
+                .then_with(|| a.type_scope_name.cmp(&b.type_scope_name))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sdk(json: &str) -> Sdk {
+        Sdk::from_json(json, "test").unwrap()
+    }
+
+    /// `(scope, class, name)` of every field at `offset`, in index order.
+    fn listed(index: &HashMap<usize, Vec<FieldEntry>>, offset: usize) -> Vec<(&str, &str, &str)> {
+        index[&offset]
+            .iter()
+            .map(|field| {
+                (
+                    field.type_scope_name.as_str(),
+                    field.class_name.as_str(),
+                    field.name.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    const BASE: &str = r#"{
+        "client": {
+            "C_A": {
+                "m_a": {"offset": 16, "type_": "int32"},
+                "m_b": {"offset": 20, "type_": "int32"}
+            },
+            "C_B": {"m_c": {"offset": 8, "type_": "bool"}}
+        }
+    }"#;
+    const PATCH: &str = r#"{
+        "client": {"C_A": {"m_a": {"offset": 24, "type_": "int64"}}},
+        "server": {"C_S": {"m_s": {"offset": 4, "type_": "float32"}}}
+    }"#;
+
+    #[test]
+    fn merge_replaces_patched_classes() {
+        let mut base = sdk(BASE);
+        let overridden = base.merge(sdk(PATCH), false);
+        assert_eq!(overridden, [("client".to_string(), "C_A".to_string())]);
+        let class = &base.type_scopes["client"].classes["C_A"];
+        assert_eq!(class.fields.len(), 1);
+        assert_eq!(class.fields["m_a"].offset, 24);
+        assert!(base.type_scopes["client"].classes.contains_key("C_B"));
+        assert!(base.has_class("C_S"));
+    }
+
+    #[test]
+    fn merge_fields_keeps_unpatched_fields() {
+        let mut base = sdk(BASE);
+        let overridden = base.merge(sdk(PATCH), true);
+        assert_eq!(overridden, [("client".to_string(), "C_A".to_string())]);
+        let class = &base.type_scopes["client"].classes["C_A"];
+        assert_eq!(class.fields["m_a"].offset, 24);
+        assert_eq!(class.fields["m_a"].type_, "int64");
+        assert_eq!(class.fields["m_b"].offset, 20);
+    }
+
+    const SCOPES: &str = r#"{
+        "client": {"C_A": {
+            "m_a": {"offset": 16, "type_": "int32"},
+            "m_b": {"offset": 16, "type_": "int32"}
+        }},
+        "server": {"C_A": {"m_a": {"offset": 16, "type_": "int64"}}},
+        "engine": {"C_A": {"m_b": {"offset": 16, "type_": "int32"}}}
+    }"#;
+
+    #[test]
+    fn find_type_conflicts_groups_by_name_in_any_order() {
+        for scope_order in [vec![], vec!["client".to_string()]] {
+            let index = build_index(&sdk(SCOPES), &scope_order);
+            let conflicts = find_type_conflicts(&index);
+            assert_eq!(conflicts.len(), 1, "{scope_order:?}");
+            assert_eq!((conflicts[0].offset, conflicts[0].name), (16, "m_a"));
+            let mut types: Vec<_> = conflicts[0]
+                .fields
+                .iter()
+                .map(|field| field.type_.as_str())
+                .collect();
+            types.sort();
+            assert_eq!(types, ["int32", "int64"]);
+        }
+    }
+
+    #[test]
+    fn sort_by_scope_order_puts_listed_scopes_first() {
+        let index = build_index(&sdk(SCOPES), &[]);
+        assert_eq!(
+            listed(&index, 16),
+            [
+                ("client", "C_A", "m_a"),
+                ("server", "C_A", "m_a"),
+                ("client", "C_A", "m_b"),
+                ("engine", "C_A", "m_b"),
+            ]
+        );
+        let mut index = index;
+        sort_by_scope_order(&mut index, &["server".to_string()]);
+        assert_eq!(
+            listed(&index, 16),
+            [
+                ("server", "C_A", "m_a"),
+                ("client", "C_A", "m_a"),
+                ("client", "C_A", "m_b"),
+                ("engine", "C_A", "m_b"),
+            ]
+        );
+    }
+}
@@ -1,108 +1,2047 @@
-#![feature(if_let_guard)]
-use anyhow::Result;
-use colored::*;
+mod alias;
+mod expect;
+mod export;
+mod http;
+mod layout;
+mod search;
+mod template;
+mod theme;
+mod tui;
+mod typemap;
+
+use alias::Aliases;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, ValueEnum};
 use dotenv_codegen::dotenv;
-use serde::Deserialize;
+use layout::natural_alignment;
+use memmap2::Mmap;
+use rand::seq::IteratorRandom;
+use sdk_lookup::{build_index, find_type_conflicts, make_offset_to_fields, FieldEntry, Sdk};
+use search::{fuzzy_search, glob_match};
+use serde_json::json;
 use std::{
-    collections::HashMap,
-    io::{stdin, stdout, Write},
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::{self, Write as _},
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
 };
+use template::Template;
+use theme::{Theme, ThemeName};
+use typemap::{looks_like_class, TypeMap};
+use walkdir::WalkDir;
 
-#[derive(Debug, Deserialize)]
-struct Sdk {
-    #[serde(flatten)]
-    type_scopes: HashMap<String, TypeScope>,
+/// Look up CS2 schema fields by offset.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Schema file, directory or http(s) URL to load instead of `SCHEMA_JSON`
+    #[arg(long, alias = "schema-dir")]
+    schema: Option<String>,
+    /// Schema file or directory merged over the schema class by class, replacing base classes
+    #[arg(long, value_name = "PATH")]
+    patch: Option<PathBuf>,
+    /// Merge `--patch` classes field by field instead of replacing whole classes
+    #[arg(long, requires = "patch")]
+    merge_fields: bool,
+    #[command(flatten)]
+    load: LoadOptions,
+    /// Color palette; individual elements can be overridden with `COLOR_*` variables
+    #[arg(long, value_enum, default_value_t)]
+    theme: ThemeName,
+    /// Only try to parse each schema file, exiting with the number of files that failed
+    #[arg(long)]
+    check: bool,
+    /// Check the `Class::field = offset` lines of this file against the schema and exit with
+    /// the number of mismatches
+    #[arg(long, value_name = "PATH")]
+    expect_file: Option<PathBuf>,
+    /// Exit with an error if any offset has same-named fields with different types
+    #[arg(long)]
+    fail_on_conflict: bool,
+    /// Write every field without a known type to this file, by scope and class, and exit
+    #[arg(long, value_name = "PATH")]
+    unresolved_report: Option<PathBuf>,
+    /// Print nothing and exit with 0 if a field (passing `--only`) exists at this offset, 1
+    /// otherwise
+    #[arg(long, value_name = "OFFSET", value_parser = parse_offset_arg)]
+    contains: Option<usize>,
+    /// Write the offset index to this JSON file and exit
+    #[arg(long, value_name = "PATH")]
+    dump_index: Option<PathBuf>,
+    /// Read a single schema document from stdin instead of `SCHEMA_JSON`
+    #[arg(long)]
+    stdin: bool,
+    /// File of `type = Class` lines telling which class a field type refers to
+    #[arg(long, value_name = "PATH")]
+    typemap: Option<PathBuf>,
+    /// Append every command typed at the prompt to this file, one per line
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+    /// Run the commands recorded in this file instead of starting the prompt
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+    /// Start the full-screen interface instead of the line prompt
+    #[arg(long, conflicts_with_all = ["queries", "replay"])]
+    tui: bool,
+    #[command(flatten)]
+    options: Options,
+    /// File that `alias` definitions are persisted to
+    #[arg(long, default_value = ".sdk-lookup-aliases")]
+    aliases: PathBuf,
+    /// Queries to run instead of starting the interactive prompt
+    queries: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct TypeScope {
-    #[serde(flatten)]
-    classes: HashMap<String, Class>,
+/// How schema files are found and read.
+#[derive(Debug, clap::Args)]
+struct LoadOptions {
+    /// Only load `.json` files directly inside the schema directory
+    #[arg(long)]
+    no_recurse: bool,
+    /// Memory-map schema files instead of reading them into memory, to lower peak memory use
+    /// on very large dumps. Falls back to regular reads where mapping fails
+    #[arg(long)]
+    mmap: bool,
+    /// Only keep these type scopes, dropping the rest as each file is loaded
+    #[arg(long, value_name = "SCOPES", value_delimiter = ',')]
+    scopes: Vec<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize)]
-struct Class {
-    #[serde(flatten)]
-    fields: HashMap<String, Field>,
+/// Settings that shape how query results are printed.
+#[derive(Debug, clap::Args)]
+struct Options {
+    /// Don't print the startup banner or the end-of-session summary
+    #[arg(long, short)]
+    quiet: bool,
+    /// Output format of offset lookups
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+    /// Number of results `/` searches show
+    #[arg(long, default_value_t = 20)]
+    search_limit: usize,
+    /// Match `/` searches against field names exactly as written, without also trying them with
+    /// the `m_` prefix stripped
+    #[arg(long)]
+    literal_search: bool,
+    /// How `/` search and `find` results are laid out
+    #[arg(long, value_enum, default_value_t)]
+    group_by: GroupBy,
+    /// Print classes as `scope!Class` instead of annotating each line with its scope
+    #[arg(long)]
+    qualified: bool,
+    /// Don't annotate result lines with their scope
+    #[arg(long, env = "NO_SCOPE")]
+    no_scope: bool,
+    /// Prompt template; `{hint}` expands to the input hint and `{state}` to active settings
+    #[arg(
+        long,
+        env = "PROMPT_TEMPLATE",
+        default_value = "enter offset {hint}{state}: "
+    )]
+    prompt: String,
+    /// Line format of offset lookup results, e.g. `{offset:#x} {class}::{name} : {type} [{scope}]`
+    #[arg(long, value_parser = Template::parse)]
+    template: Option<Template>,
+    /// Flag looked up fields whose offset isn't aligned to their type's natural alignment
+    #[arg(long)]
+    check_align: bool,
+    /// On a miss, list populated offsets within this distance (0x20 if no value is given)
+    #[arg(
+        long,
+        value_name = "WINDOW",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0x20",
+        value_parser = parse_offset_arg
+    )]
+    suggest: Option<usize>,
+    /// List fields of these scopes first when an offset resolves in several, in this order;
+    /// unlisted scopes follow alphabetically
+    #[arg(long, value_name = "SCOPES", value_delimiter = ',')]
+    scope_order: Vec<String>,
+    /// Only show fields of these classes in offset lookups
+    #[arg(long, value_name = "CLASSES", value_delimiter = ',')]
+    only: Vec<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize)]
-struct Field {
-    offset: usize,
-    type_: String,
+impl Options {
+    /// Whether `field` passes `--only`.
+    fn shows(&self, field: &FieldEntry) -> bool {
+        self.only.is_empty() || self.only.contains(&field.class_name)
+    }
 }
 
-#[derive(Debug)]
-struct FieldEntry {
-    name: String,
-    type_: String,
-    class_name: String,
+/// Loads a single schema file, or merges every `.json` file found under a directory.
+///
+/// Files are loaded in sorted path order. A scope split across files gets the classes of all of
+/// them; when several files define the same class in a scope the later file wins, with a
+/// warning, so precedence is the same on every run.
+fn load_schema(path: &Path, options: &LoadOptions) -> Result<Sdk> {
+    let mut sdk = Sdk {
+        type_scopes: HashMap::new(),
+    };
+    for file in schema_files(path, options)? {
+        let overridden = sdk.merge(load_schema_file(&file, options)?, false);
+        for (type_scope_name, class_name) in overridden {
+            eprintln!(
+                "{} redefines {type_scope_name}!{class_name} from an earlier file",
+                file.display()
+            );
+        }
+    }
+    Ok(sdk)
+}
+
+fn load_schema_file(path: &Path, options: &LoadOptions) -> Result<Sdk> {
+    let mapped = options
+        .mmap
+        .then(|| {
+            File::open(path)
+                .and_then(|file| {
+                    // SAFETY: the mapping is only read, and only until parsing finishes. Bytes
+                    // rewritten in place meanwhile at worst yield a parse error, but a file
+                    // truncated underneath us raises SIGBUS on the next read past its new end,
+                    // so `--mmap` must not be used on dumps another process may be replacing.
+                    unsafe { Mmap::map(&file) }
+                })
+                .ok()
+        })
+        .flatten();
+    let read;
+    let bytes = match &mapped {
+        Some(mapped) => &mapped[..],
+        None => {
+            read = std::fs::read(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            &read[..]
+        }
+    };
+    let text = std::str::from_utf8(bytes)
+        .with_context(|| format!("{} is not valid UTF-8", path.display()))?;
+    let mut sdk = Sdk::from_json(text, &path.display().to_string())?;
+    retain_scopes(&mut sdk, options);
+    Ok(sdk)
+}
+
+/// Drops the scopes `--scopes` doesn't ask for; without `--scopes` every scope is kept.
+fn retain_scopes(sdk: &mut Sdk, options: &LoadOptions) {
+    if !options.scopes.is_empty() {
+        sdk.type_scopes
+            .retain(|type_scope_name, _| options.scopes.contains(type_scope_name));
+    }
+}
+
+/// Where the schema is read from and how, kept so `reload` can repeat it.
+struct SchemaSource {
+    /// Schema file, directory or URL.
+    location: String,
+    load: LoadOptions,
+    patch: Option<PathBuf>,
+    merge_fields: bool,
+    quiet: bool,
+}
+
+impl SchemaSource {
+    fn load(&self) -> Result<Sdk> {
+        let sdk = if http::is_url(&self.location) {
+            Sdk::from_json(&http::fetch_schema(&self.location)?, &self.location)?
+        } else {
+            load_schema(Path::new(&self.location), &self.load)?
+        };
+        self.finish(sdk)
+    }
+
+    /// Applies `--scopes` and `--patch` to a freshly read schema.
+    fn finish(&self, mut sdk: Sdk) -> Result<Sdk> {
+        retain_scopes(&mut sdk, &self.load);
+        for type_scope_name in &self.load.scopes {
+            if !sdk.type_scopes.contains_key(type_scope_name) {
+                eprintln!("scope {type_scope_name} was requested with --scopes but never appeared");
+            }
+        }
+        if let Some(path) = &self.patch {
+            let patch = load_schema(path, &self.load)?;
+            for (type_scope_name, class_name) in sdk.merge(patch, self.merge_fields) {
+                if !self.quiet {
+                    eprintln!(
+                        "{} overrides {type_scope_name}!{class_name}",
+                        path.display()
+                    );
+                }
+            }
+        }
+        Ok(sdk)
+    }
+}
+
+/// Lists the schema files `load_schema` would load from `path`, in load order.
+fn schema_files(path: &Path, options: &LoadOptions) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let max_depth = if options.no_recurse { 1 } else { usize::MAX };
+    let mut files = Vec::new();
+    for entry in WalkDir::new(path).max_depth(max_depth).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.path().extension() == Some("json".as_ref()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Parses every schema file on its own and reports PASS/FAIL for each, returning the number of
+/// failures. A URL is downloaded and checked as a single document.
+fn check_schema_files(source: &str, options: &LoadOptions) -> Result<usize> {
+    if http::is_url(source) {
+        let checked = http::fetch_schema(source).and_then(|text| Sdk::from_json(&text, source));
+        return Ok(match checked {
+            Ok(_) => {
+                println!("PASS {source}");
+                0
+            }
+            Err(e) => {
+                println!("FAIL {e:#}");
+                1
+            }
+        });
+    }
+    let mut failures = 0;
+    for file in schema_files(Path::new(source), options)? {
+        match load_schema_file(&file, options) {
+            Ok(_) => println!("PASS {}", file.display()),
+            Err(e) => {
+                failures += 1;
+                println!("FAIL {e:#}");
+            }
+        }
+    }
+    Ok(failures)
+}
+
+/// Several differently named fields of one class declared at the same offset, usually a union
+/// or a generator artifact.
+struct SharedOffset {
     type_scope_name: String,
+    class_name: String,
+    offset: usize,
+    names: Vec<String>,
 }
 
-fn make_offset_to_fields(sdk: &Sdk) -> HashMap<usize, Vec<FieldEntry>> {
-    let mut offset_to_fields = HashMap::new();
+fn find_shared_offsets(sdk: &Sdk) -> Vec<SharedOffset> {
+    let mut shared = Vec::new();
     for (type_scope_name, type_scope) in &sdk.type_scopes {
         for (class_name, class) in &type_scope.classes {
-            for (field_name, field) in &class.fields {
-                offset_to_fields
+            let mut by_offset: HashMap<usize, Vec<String>> = HashMap::new();
+            for (field_name, field) in class
+                .fields
+                .iter()
+                .filter(|(_, field)| field.bit_offset.is_none())
+            {
+                by_offset
                     .entry(field.offset)
-                    .or_insert_with(Vec::new)
-                    .push(FieldEntry {
-                        name: field_name.clone(),
-                        type_: field.type_.clone(),
-                        class_name: class_name.clone(),
+                    .or_default()
+                    .push(field_name.clone());
+            }
+            for (offset, mut names) in by_offset {
+                if names.len() > 1 {
+                    names.sort();
+                    shared.push(SharedOffset {
                         type_scope_name: type_scope_name.clone(),
+                        class_name: class_name.clone(),
+                        offset,
+                        names,
                     });
+                }
+            }
+        }
+    }
+    shared.sort_by(|a, b| {
+        (&a.type_scope_name, &a.class_name, a.offset).cmp(&(
+            &b.type_scope_name,
+            &b.class_name,
+            b.offset,
+        ))
+    });
+    shared
+}
+
+fn parse_offset_arg(arg: &str) -> Result<usize, String> {
+    parse_offset(arg).ok_or_else(|| format!("invalid hex offset {arg:?}"))
+}
+
+/// Commands of the interactive prompt, as listed by `help`.
+const COMMANDS: &[(&str, &str)] = &[
+    (
+        "addr",
+        "addr <address>: resolve a runtime address using base and anchor",
+    ),
+    (
+        "alias",
+        "alias [<name> <offset>]: name an offset, or list aliases",
+    ),
+    (
+        "anchor",
+        "anchor [<offset>|off]: set the offset addr and @+N/@-N are relative to",
+    ),
+    (
+        "array",
+        "array <base> <stride> <offset>: find the element an offset falls into",
+    ),
+    (
+        "base",
+        "base [<address>|off]: set the module base addr subtracts",
+    ),
+    (
+        "class",
+        "class <pattern>: print the layout of matching classes",
+    ),
+    (
+        "compare",
+        "compare <offset>: show each scope's fields at an offset",
+    ),
+    (
+        "confirm",
+        "confirm [Class] <offset> <name>: check that a field sits at an offset",
+    ),
+    ("count", "count <offset>: count the fields at an offset"),
+    (
+        "dangling",
+        "dangling: list field types referring to classes that aren't loaded",
+    ),
+    (
+        "explain",
+        "explain <offset>: narrate how an offset resolves",
+    ),
+    (
+        "find",
+        "find [scope=..] [type=..] [class=..] [name=..]: list fields matching every pattern",
+    ),
+    (
+        "goto",
+        "goto <offset>: look up an offset and anchor @+N/@-N lookups there",
+    ),
+    ("help", "help: show this list"),
+    (
+        "mask",
+        "mask [<bits>|off]: clear low bits of looked up offsets",
+    ),
+    ("random", "random [N]: resolve randomly picked offsets"),
+    (
+        "reload",
+        "reload: read the schema again, keeping session state",
+    ),
+    ("raw", "raw <offset>: show stored fields before masking"),
+    ("typeof", "typeof Class::field: print a field's type"),
+    ("types", "types: list field types by use"),
+    (
+        "unions",
+        "unions: list classes with several fields at one offset",
+    ),
+];
+
+/// Counts the distinct field names among the fields at one offset, in any order. More than one
+/// name marks a collision between fields that most likely describe different memory rather than
+/// one inherited member.
+fn count_name_groups<'a>(fields: impl IntoIterator<Item = &'a FieldEntry>) -> usize {
+    // Bitfield members share their byte by design, so they don't count as a collision.
+    fields
+        .into_iter()
+        .filter(|field| field.bit_offset.is_none())
+        .map(|field| &field.name)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Parses a hex offset as pasted from other tools: surrounding whitespace, a leading `+`, a `0x`
+/// prefix in either case and leading zeros are all accepted.
+fn parse_offset(input: &str) -> Option<usize> {
+    let input = input.trim();
+    let input = input.strip_prefix('+').unwrap_or(input);
+    let digits = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    usize::from_str_radix(digits, 16).ok()
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum GroupBy {
+    /// One line per hit, with its offset
+    #[default]
+    Offset,
+    /// Hits listed under a header for their class
+    Class,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+    /// Text with type, member and scope aligned in columns
+    Table,
+}
+
+/// The loaded schema together with the output settings every command shares.
+struct Session {
+    sdk: Sdk,
+    /// Where `sdk` was read from, for `reload`; `None` when it came from stdin.
+    schema_source: Option<SchemaSource>,
+    offset_to_fields: HashMap<usize, Vec<FieldEntry>>,
+    theme: Theme,
+    aliases: Aliases,
+    typemap: TypeMap,
+    options: Options,
+    stats: Stats,
+    shared_offsets: Vec<SharedOffset>,
+    /// Number of low offset bits cleared before lookups, set with `mask`.
+    mask_bits: u32,
+    /// Runtime address offsets are relative to, set with `base`.
+    base: Option<usize>,
+    /// Offset of a known field, set with `anchor`.
+    anchor: Option<usize>,
+    /// Collects command output instead of printing it while the TUI runs a command.
+    captured: RefCell<Option<String>>,
+}
+
+/// `println!` for command output, which `Session::capture` can collect instead of printing.
+macro_rules! say {
+    ($session:expr) => {
+        $session.say(format_args!(""))
+    };
+    ($session:expr, $($arg:tt)*) => {
+        $session.say(format_args!($($arg)*))
+    };
+}
+
+/// Counters for the end-of-session summary.
+#[derive(Default)]
+struct Stats {
+    queries: usize,
+    hits: usize,
+    misses: usize,
+    offsets: HashSet<usize>,
+}
+
+impl Session {
+    fn new(
+        sdk: Sdk,
+        schema_source: Option<SchemaSource>,
+        theme: Theme,
+        aliases: Aliases,
+        typemap: TypeMap,
+        options: Options,
+    ) -> Self {
+        let offset_to_fields = build_index(&sdk, &options.scope_order);
+        let shared_offsets = find_shared_offsets(&sdk);
+        Session {
+            sdk,
+            schema_source,
+            offset_to_fields,
+            theme,
+            aliases,
+            typemap,
+            options,
+            stats: Stats::default(),
+            shared_offsets,
+            mask_bits: 0,
+            base: None,
+            anchor: None,
+            captured: RefCell::new(None),
+        }
+    }
+
+    /// Prints one line of command output, or collects it during `capture`.
+    fn say(&self, line: fmt::Arguments) {
+        match &mut *self.captured.borrow_mut() {
+            Some(captured) => {
+                let _ = writeln!(captured, "{line}");
             }
+            None => println!("{line}"),
         }
     }
-    offset_to_fields
-}
-
-fn run_interactive_loop(offset_to_fields: &HashMap<usize, Vec<FieldEntry>>) -> Result<()> {
-    let mut input = String::new();
-    loop {
-        print!("enter offset {}: ", "(hex)".dimmed());
-        stdout().flush()?;
-        input.clear();
-        stdin().read_line(&mut input)?;
-        input = input.trim().to_string();
-        if input.is_empty() || input == "exit" {
-            break;
-        }
-        if input.starts_with("0x") {
-            input = input[2..].to_string();
-        }
-        match usize::from_str_radix(&input, 16) {
-            Ok(offset) => {
-                if let Some(fields) = offset_to_fields.get(&offset) {
-                    for field in fields {
-                        println!(
-                            "{} {}{}{} ({})",
-                            field.type_.purple(),
-                            field.class_name.yellow(),
-                            "::".dimmed(),
-                            field.name,
-                            field.type_scope_name.dimmed(),
-                        );
+
+    /// Runs `run` and returns everything it printed as command output.
+    fn capture(&mut self, run: impl FnOnce(&mut Self)) -> String {
+        let previous = self.captured.replace(Some(String::new()));
+        run(self);
+        self.captured.replace(previous).unwrap_or_default()
+    }
+
+    fn print_help(&self) {
+        say!(self, "<offset> or <alias>: list the fields at an offset");
+        say!(self, "/<query>: fuzzy search field names");
+        say!(
+            self,
+            ":<offset> or :<alias>: look up even if the alias is also a command name"
+        );
+        say!(
+            self,
+            "offsetof(Class, field): print the offset each scope declares the field at"
+        );
+        for (_, usage) in COMMANDS {
+            say!(self, "{usage}");
+        }
+        say!(
+            self,
+            "A command name takes precedence over an alias of the same name."
+        );
+    }
+
+    /// Runs one line of input. A leading `/` searches and a leading `:` always looks the rest
+    /// up as an offset or alias; otherwise a command name wins over an alias of the same name.
+    fn run_command(&mut self, input: &str) {
+        self.stats.queries += 1;
+        if let Some(query) = input.strip_prefix('/') {
+            self.print_search(query.trim());
+            return;
+        }
+        if let Some(input) = input.strip_prefix(':') {
+            self.print_fields_at(input.trim());
+            return;
+        }
+        if let Some(member) = input
+            .strip_prefix("offsetof(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            self.print_offsetof(member);
+            return;
+        }
+        let (command, rest) = input.split_once(' ').unwrap_or((input, ""));
+        match command {
+            "alias" => self.alias(rest),
+            "array" => self.resolve_array_element(rest),
+            "class" => self.print_classes(rest),
+            "compare" => self.compare_scopes(rest),
+            "confirm" => self.confirm(rest),
+            "count" => self.print_field_count(rest),
+            "dangling" => self.print_dangling_types(),
+            "explain" => self.explain(rest),
+            "find" => self.find(rest),
+            "goto" => self.goto(rest),
+            "help" => self.print_help(),
+            "mask" => self.set_mask(rest),
+            "raw" => self.print_raw(rest),
+            "addr" => self.resolve_address(rest),
+            "anchor" => self.set_anchor(rest),
+            "base" => self.set_base(rest),
+            "random" => self.print_random_offsets(rest),
+            "reload" => self.reload(),
+            "typeof" => self.print_field_type(rest),
+            "types" => self.print_types(),
+            "unions" => self.print_shared_offsets(),
+            _ => self.print_fields_at(input),
+        }
+    }
+
+    /// `reload` reads the schema again from where it was loaded, keeping aliases, anchor and
+    /// other session state. If loading fails the current schema stays in place.
+    fn reload(&mut self) {
+        let Some(schema_source) = &self.schema_source else {
+            say!(self, "the schema was read from stdin and can't be reloaded");
+            return;
+        };
+        match schema_source.load() {
+            Ok(sdk) => {
+                self.offset_to_fields = build_index(&sdk, &self.options.scope_order);
+                self.shared_offsets = find_shared_offsets(&sdk);
+                self.sdk = sdk;
+                let classes: usize = self
+                    .sdk
+                    .type_scopes
+                    .values()
+                    .map(|type_scope| type_scope.classes.len())
+                    .sum();
+                say!(
+                    self,
+                    "reloaded {} scopes, {classes} classes, {} offsets",
+                    self.sdk.type_scopes.len(),
+                    self.offset_to_fields.len()
+                );
+            }
+            Err(e) => say!(self, "reload failed, keeping the loaded schema: {e:#}"),
+        }
+    }
+
+    /// Parses an offset, accepting alias names in place of hex values and, once an anchor is
+    /// set, `@+N`/`@-N` relative to it. A bare `+N` stays absolute, as pasted offsets often
+    /// carry one.
+    fn resolve_offset(&self, input: &str) -> Option<usize> {
+        self.resolve_offset_explained(input)
+            .map(|(offset, _)| offset)
+    }
+
+    /// Like `resolve_offset`, but also describes which rule resolved the input, for `explain`.
+    fn resolve_offset_explained(&self, input: &str) -> Option<(usize, String)> {
+        let input = input.trim();
+        if let Some(relative) = input.strip_prefix('@') {
+            let anchor = self.anchor?;
+            let relative = relative.trim_start();
+            if let Some(delta) = relative.strip_prefix('+') {
+                let delta = parse_offset(delta.trim())?;
+                let offset = anchor.checked_add(delta)?;
+                return Some((
+                    offset,
+                    format!("{input:?} is anchor 0x{anchor:x} + 0x{delta:x} = 0x{offset:x}"),
+                ));
+            }
+            if let Some(delta) = relative.strip_prefix('-') {
+                let delta = parse_offset(delta.trim())?;
+                let offset = anchor.checked_sub(delta)?;
+                return Some((
+                    offset,
+                    format!("{input:?} is anchor 0x{anchor:x} - 0x{delta:x} = 0x{offset:x}"),
+                ));
+            }
+            return None;
+        }
+        if let Some(offset) = self.aliases.get(input) {
+            return Some((offset, format!("{input:?} is an alias for 0x{offset:x}")));
+        }
+        let offset = parse_offset(input)?;
+        Some((
+            offset,
+            format!("{input:?} parsed as hex offset 0x{offset:x}"),
+        ))
+    }
+
+    /// `alias` lists all aliases, `alias <name> <offset>` defines one.
+    fn alias(&mut self, args: &str) {
+        if args.is_empty() {
+            for (name, offset) in self.aliases.iter() {
+                say!(self, "{name} = 0x{offset:x}");
+            }
+            return;
+        }
+        let Some((name, offset)) = args.split_once(' ') else {
+            say!(self, "usage: alias <name> <offset>");
+            return;
+        };
+        let Some(offset) = self.resolve_offset(offset.trim()) else {
+            say!(self, "invalid offset");
+            return;
+        };
+        if let Err(e) = self.aliases.set(name, offset) {
+            say!(self, "{e:#}");
+        } else if COMMANDS.iter().any(|(command, _)| *command == name) {
+            say!(
+                self,
+                "{name} is also a command; look the alias up as :{name}"
+            );
+        }
+    }
+
+    /// `mask <bits>` rounds later lookups down to a multiple of `2^bits`; `mask off` disables it.
+    fn set_mask(&mut self, bits: &str) {
+        match bits {
+            "" => say!(self, "mask: {} bits", self.mask_bits),
+            "off" => self.mask_bits = 0,
+            _ => match bits.parse() {
+                Ok(bits) if bits < usize::BITS => self.mask_bits = bits,
+                _ => say!(
+                    self,
+                    "invalid mask, expected a bit count below {}",
+                    usize::BITS
+                ),
+            },
+        }
+    }
+
+    /// `base <address>` sets the runtime address offsets are relative to for `addr`.
+    fn set_base(&mut self, address: &str) {
+        match address {
+            "" => match self.base {
+                Some(base) => say!(self, "base: 0x{base:x}"),
+                None => say!(self, "base: not set"),
+            },
+            "off" => self.base = None,
+            _ => match parse_offset(address) {
+                Some(base) => self.base = Some(base),
+                None => say!(self, "invalid address"),
+            },
+        }
+    }
+
+    /// `anchor <offset>` marks a known field that `addr` reports its results relative to.
+    fn set_anchor(&mut self, offset: &str) {
+        match offset {
+            "" => match self.anchor {
+                Some(anchor) => say!(self, "anchor: 0x{anchor:x}"),
+                None => say!(self, "anchor: not set"),
+            },
+            "off" => self.anchor = None,
+            _ => match self.resolve_offset(offset) {
+                Some(anchor) => self.anchor = Some(anchor),
+                None => say!(self, "invalid offset"),
+            },
+        }
+    }
+
+    /// `array <base> <stride> <offset>` treats an array of `stride`-sized elements as starting
+    /// at `base`, reports which element `offset` falls into, and resolves the matching offset
+    /// within the first element, which is the one the schema names.
+    fn resolve_array_element(&mut self, args: &str) {
+        let parsed: Vec<_> = args
+            .split_whitespace()
+            .map(|arg| self.resolve_offset(arg))
+            .collect();
+        let [Some(base), Some(stride), Some(offset)] = parsed[..] else {
+            say!(self, "usage: array <base> <stride> <offset>");
+            return;
+        };
+        if stride == 0 {
+            say!(self, "stride must not be zero");
+            return;
+        }
+        let Some(relative) = offset.checked_sub(base) else {
+            say!(
+                self,
+                "offset 0x{offset:x} is below the array base 0x{base:x}"
+            );
+            return;
+        };
+        let (element, within) = (relative / stride, relative % stride);
+        say!(
+            self,
+            "element {element}, +0x{within:x} -> 0x{:x}",
+            base + within
+        );
+        self.lookup(base + within);
+    }
+
+    /// Resolves C++ `offsetof(Class, field)` to the offset each scope declares the field at.
+    fn print_offsetof(&self, member: &str) {
+        let Some((class_name, field_name)) = member.split_once(',') else {
+            say!(self, "usage: offsetof(Class, field)");
+            return;
+        };
+        let (class_name, field_name) = (class_name.trim(), field_name.trim());
+        let mut found: Vec<_> = self
+            .offset_to_fields
+            .iter()
+            .flat_map(|(offset, fields)| fields.iter().map(move |field| (*offset, field)))
+            .filter(|(_, field)| field.class_name == class_name && field.name == field_name)
+            .collect();
+        found.sort_by(|a, b| a.1.type_scope_name.cmp(&b.1.type_scope_name));
+        if found.is_empty() {
+            say!(
+                self,
+                "{class_name}::{field_name} is not declared in any scope"
+            );
+        }
+        for (offset, field) in found {
+            say!(
+                self,
+                "{} {}",
+                self.theme.hint.paint(&format!("0x{offset:x}")),
+                self.format_field(field)
+            );
+        }
+    }
+
+    /// `confirm [Class] <offset> <name>` checks that a field named `name` sits exactly at
+    /// `offset`, in `Class` if given, and lists the classes where it does.
+    fn confirm(&self, args: &str) {
+        let args: Vec<_> = args.split_whitespace().collect();
+        let (class_name, offset, name) = match args[..] {
+            [offset, name] => (None, offset, name),
+            [class_name, offset, name] => (Some(class_name), offset, name),
+            _ => {
+                say!(self, "usage: confirm [Class] <offset> <name>");
+                return;
+            }
+        };
+        let Some(offset) = self.resolve_offset(offset) else {
+            say!(self, "invalid offset");
+            return;
+        };
+        let fields: Vec<_> = self
+            .offset_to_fields
+            .get(&offset)
+            .into_iter()
+            .flatten()
+            .filter(|field| field.name == name)
+            .filter(|field| class_name.is_none_or(|class_name| field.class_name == class_name))
+            .collect();
+        if let Format::Json = self.options.format {
+            say!(
+                self,
+                "{}",
+                json!({
+                    "offset": offset,
+                    "name": name,
+                    "confirmed": !fields.is_empty(),
+                    "fields": fields,
+                })
+            );
+            return;
+        }
+        if fields.is_empty() {
+            let class_name =
+                class_name.map_or(String::new(), |class_name| format!(" in {class_name}"));
+            say!(self, "no: no field {name} at 0x{offset:x}{class_name}");
+            return;
+        }
+        say!(self, "yes: {name} is at 0x{offset:x} in");
+        for field in fields {
+            say!(self, "  {}", self.format_field(field));
+        }
+    }
+
+    /// `goto <offset>` looks the offset up and makes it the anchor, so `@+N` walks from there.
+    fn goto(&mut self, input: &str) {
+        let Some(offset) = self.resolve_offset(input) else {
+            say!(self, "invalid offset");
+            return;
+        };
+        self.lookup(offset);
+        self.anchor = Some(offset);
+    }
+
+    /// Turns an absolute runtime address into an offset by subtracting `base`, reports where it
+    /// lies relative to `anchor`, and resolves it.
+    fn resolve_address(&mut self, address: &str) {
+        let Some(address) = parse_offset(address) else {
+            say!(self, "invalid address");
+            return;
+        };
+        let Some(base) = self.base else {
+            say!(self, "set the module base with `base <address>` first");
+            return;
+        };
+        let Some(offset) = address.checked_sub(base) else {
+            say!(self, "address 0x{address:x} is below base 0x{base:x}");
+            return;
+        };
+        say!(self, "0x{address:x} - base 0x{base:x} = 0x{offset:x}");
+        if let Some(anchor) = self.anchor {
+            match offset.checked_sub(anchor) {
+                Some(delta) => {
+                    say!(
+                        self,
+                        "0x{offset:x} - anchor 0x{anchor:x} = anchor + 0x{delta:x}"
+                    )
+                }
+                None => say!(
+                    self,
+                    "0x{offset:x} - anchor 0x{anchor:x} = anchor - 0x{:x}",
+                    anchor - offset
+                ),
+            }
+        }
+        self.lookup(offset);
+    }
+
+    /// `raw <offset>` shows the fields stored at exactly this offset, ignoring filters, next to
+    /// the offset a regular lookup would use once `mask` is applied.
+    fn print_raw(&self, input: &str) {
+        let Some(offset) = self.resolve_offset(input) else {
+            say!(self, "invalid offset");
+            return;
+        };
+        let print_stored = |offset: usize| match self.offset_to_fields.get(&offset) {
+            Some(fields) => {
+                for field in fields {
+                    say!(self, "  {}", self.format_field(field));
+                }
+            }
+            None => say!(self, "  nothing stored"),
+        };
+        say!(self, "stored at 0x{offset:x} ({offset}):");
+        print_stored(offset);
+        let masked = self.masked(offset);
+        if masked != offset {
+            say!(
+                self,
+                "looked up as 0x{masked:x} ({masked}) after masking {} bits:",
+                self.mask_bits
+            );
+            print_stored(masked);
+        }
+    }
+
+    /// Applies the `mask` setting to a looked up offset.
+    fn masked(&self, offset: usize) -> usize {
+        offset & !((1 << self.mask_bits) - 1)
+    }
+
+    fn print_fields_at(&mut self, input: &str) {
+        let Some(offset) = self.resolve_offset(input) else {
+            match self.options.format {
+                Format::Text | Format::Table => say!(self, "invalid offset"),
+                Format::Json => {
+                    say!(
+                        self,
+                        "{}",
+                        json!({ "input": input, "error": "invalid offset" })
+                    )
+                }
+            }
+            return;
+        };
+        self.lookup(offset);
+    }
+
+    /// Resolves an offset the user asked for, applying `mask` and counting it in the session
+    /// stats.
+    fn lookup(&mut self, mut offset: usize) {
+        let masked = self.masked(offset);
+        if masked != offset {
+            if let Format::Text | Format::Table = self.options.format {
+                say!(
+                    self,
+                    "{}",
+                    self.theme
+                        .hint
+                        .paint(&format!("0x{offset:x} masked to 0x{masked:x}"))
+                );
+            }
+            offset = masked;
+        }
+        if self.offset_to_fields.contains_key(&offset) {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        self.stats.offsets.insert(offset);
+        self.print_offset(offset);
+    }
+
+    fn print_offset(&self, offset: usize) {
+        let fields: Vec<_> = self
+            .offset_to_fields
+            .get(&offset)
+            .into_iter()
+            .flatten()
+            .filter(|field| self.is_shown(field))
+            .collect();
+        let hidden = self.hidden_count(offset);
+        if let Format::Json = self.options.format {
+            say!(
+                self,
+                "{}",
+                json!({ "offset": offset, "fields": fields, "hidden": hidden })
+            );
+            return;
+        }
+        if fields.is_empty() && hidden > 0 {
+            say!(
+                self,
+                "{hidden} fields at 0x{offset:x} hidden by active filters"
+            );
+        } else if fields.is_empty() {
+            say!(self, "no field at offset 0x{:x}", offset);
+            if let Some(window) = self.options.suggest {
+                self.print_nearby(offset, window);
+            }
+        }
+        if let Some(template) = &self.options.template {
+            // A template describes each line completely, so none of the annotations below apply.
+            for field in &fields {
+                say!(self, "{}", template.render(offset, field));
+            }
+            return;
+        }
+        let lines: Vec<_> = match self.options.format {
+            Format::Table => self.format_table(&fields),
+            _ => fields
+                .iter()
+                .map(|field| self.format_field(field))
+                .collect(),
+        };
+        for (field, line) in fields.iter().zip(lines) {
+            let line = match field.bit_offset {
+                Some(bit) if !matches!(self.options.format, Format::Table) => format!(
+                    "{} {line}",
+                    self.theme.hint.paint(&format!("0x{offset:x}:bit{bit}"))
+                ),
+                _ => line,
+            };
+            let alignment =
+                natural_alignment(&field.type_).filter(|&align| !offset.is_multiple_of(align));
+            match alignment {
+                Some(align) if self.options.check_align => say!(
+                    self,
+                    "{line} {}",
+                    self.theme.hint.paint(&format!(
+                        "misaligned: 0x{offset:x} not {align}-byte aligned"
+                    ))
+                ),
+                _ => say!(self, "{line}"),
+            }
+        }
+        let names = count_name_groups(fields);
+        if names > 1 {
+            say!(
+                self,
+                "{}",
+                self.theme.hint.paint(&format!(
+                    "{names} different field names at this offset, fields under different names are likely unrelated"
+                ))
+            );
+        }
+    }
+
+    /// Whether `field` passes the output filters given on the command line.
+    fn is_shown(&self, field: &FieldEntry) -> bool {
+        self.options.shows(field)
+    }
+
+    /// Counts the fields at `offset` that the output filters hide.
+    fn hidden_count(&self, offset: usize) -> usize {
+        self.offset_to_fields.get(&offset).map_or(0, |fields| {
+            fields.iter().filter(|field| !self.is_shown(field)).count()
+        })
+    }
+
+    /// Lists populated offsets within `window` of a missed offset, closest first.
+    fn print_nearby(&self, offset: usize, window: usize) {
+        let mut nearby: Vec<_> = self
+            .offset_to_fields
+            .keys()
+            .copied()
+            .filter(|&other| other != offset && other.abs_diff(offset) <= window)
+            .collect();
+        nearby.sort_by_key(|&other| (other.abs_diff(offset), other));
+        for other in nearby {
+            let distance = if other < offset {
+                format!("-0x{:x}", offset - other)
+            } else {
+                format!("+0x{:x}", other - offset)
+            };
+            for field in self.offset_to_fields[&other].iter() {
+                if self.is_shown(field) {
+                    say!(
+                        self,
+                        "  {} {}",
+                        self.theme.hint.paint(&format!("{distance} 0x{other:x}")),
+                        self.format_field(field)
+                    );
+                }
+            }
+        }
+    }
+
+    fn format_field(&self, field: &FieldEntry) -> String {
+        let type_ = self.theme.type_.paint(&field.type_);
+        let class_name = self.qualified_name(&field.type_scope_name, &field.class_name);
+        let separator = self.theme.separator.paint("::");
+        let scope = self.scope_note(&field.type_scope_name);
+        format!("{type_} {class_name}{separator}{}{scope}", field.name)
+    }
+
+    /// Names a class as `scope!Class` with `--qualified`, and as just `Class` otherwise, in
+    /// which case `scope_note` annotates the scope.
+    fn qualified_name(&self, scope: &str, class_name: &str) -> String {
+        let class_name = self.theme.class.paint(class_name);
+        if self.options.qualified {
+            let bang = self.theme.separator.paint("!");
+            format!("{}{bang}{class_name}", self.theme.scope.paint(scope))
+        } else {
+            class_name.to_string()
+        }
+    }
+
+    /// The ` (scope)` suffix that follows an unqualified name, empty with `--qualified` or
+    /// `--no-scope`.
+    fn scope_note(&self, scope: &str) -> String {
+        if self.options.qualified || self.options.no_scope {
+            String::new()
+        } else {
+            format!(" ({})", self.theme.scope.paint(scope))
+        }
+    }
+
+    /// `find scope=client type=CHandle* class=C_Weapon* name=m_h*` lists every field matching
+    /// all of the given wildcard patterns, ordered by offset.
+    fn find(&self, args: &str) {
+        let mut filters = Vec::new();
+        for arg in args.split_whitespace() {
+            match arg.split_once('=') {
+                Some((key @ ("scope" | "type" | "class" | "name"), pattern)) => {
+                    filters.push((key, pattern))
+                }
+                _ => {
+                    say!(
+                        self,
+                        "usage: find [scope=..] [type=..] [class=..] [name=..]"
+                    );
+                    return;
+                }
+            }
+        }
+        let mut hits: Vec<_> = self
+            .offset_to_fields
+            .iter()
+            .flat_map(|(offset, fields)| fields.iter().map(move |field| (*offset, field)))
+            .filter(|(_, field)| self.is_shown(field))
+            .filter(|(_, field)| {
+                filters.iter().all(|&(key, pattern)| {
+                    let value = match key {
+                        "scope" => &field.type_scope_name,
+                        "type" => &field.type_,
+                        "class" => &field.class_name,
+                        _ => &field.name,
+                    };
+                    glob_match(pattern, value)
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            (a.0, &a.1.class_name, &a.1.name).cmp(&(b.0, &b.1.class_name, &b.1.name))
+        });
+        if let Format::Json = self.options.format {
+            let hits: Vec<_> = hits
+                .iter()
+                .map(|(offset, field)| json!({ "offset": offset, "field": field }))
+                .collect();
+            say!(self, "{}", json!({ "query": args, "hits": hits }));
+            return;
+        }
+        if hits.is_empty() {
+            say!(self, "no field matches all filters");
+        }
+        self.print_hits(&hits);
+    }
+
+    /// Formats `fields` like `format_field`, but with type, member and scope padded into aligned
+    /// columns.
+    fn format_table(&self, fields: &[&FieldEntry]) -> Vec<String> {
+        let member_width = |field: &FieldEntry| {
+            let scope = if self.options.qualified {
+                field.type_scope_name.len() + 1
+            } else {
+                0
+            };
+            scope + field.class_name.len() + 2 + field.name.len()
+        };
+        let type_width = fields.iter().map(|field| field.type_.len()).max();
+        let type_width = type_width.unwrap_or(0);
+        let members_width = fields.iter().map(|field| member_width(field)).max();
+        let members_width = members_width.unwrap_or(0);
+        // Bitfield positions get a leading column of their own, present only when needed.
+        let bit = |field: &FieldEntry| field.bit_offset.map(|bit| format!("bit{bit}"));
+        let bit_width = fields
+            .iter()
+            .filter_map(|field| bit(field))
+            .map(|bit| bit.len())
+            .max();
+        fields
+            .iter()
+            .map(|field| {
+                let bit_column = match bit_width {
+                    Some(width) => {
+                        let bit = bit(field).unwrap_or_default();
+                        let padding = " ".repeat(width - bit.len());
+                        format!("{}{padding}  ", self.theme.hint.paint(&bit))
                     }
+                    None => String::new(),
+                };
+                let type_ = self.theme.type_.paint(&field.type_);
+                let class_name = self.qualified_name(&field.type_scope_name, &field.class_name);
+                let separator = self.theme.separator.paint("::");
+                let scope = self.theme.scope.paint(&field.type_scope_name);
+                let type_padding = " ".repeat(type_width - field.type_.len());
+                let member = format!("{class_name}{separator}{}", field.name);
+                if self.options.qualified || self.options.no_scope {
+                    format!("{bit_column}{type_}{type_padding}  {member}")
                 } else {
-                    println!("no field at offset 0x{:x}", offset);
+                    let member_padding = " ".repeat(members_width - member_width(field));
+                    format!("{bit_column}{type_}{type_padding}  {member}{member_padding}  {scope}")
                 }
+            })
+            .collect()
+    }
+
+    /// Lists the fields whose names best fuzzy match `query`, with their offsets.
+    fn print_search(&self, query: &str) {
+        let hits = fuzzy_search(
+            &self.offset_to_fields,
+            query,
+            self.options.search_limit,
+            self.options.literal_search,
+            |field| self.is_shown(field),
+        );
+        if let Format::Json = self.options.format {
+            let hits: Vec<_> = hits
+                .iter()
+                .map(|hit| json!({ "offset": hit.offset, "score": hit.score, "field": hit.field }))
+                .collect();
+            say!(self, "{}", json!({ "query": query, "hits": hits }));
+            return;
+        }
+        if hits.is_empty() {
+            say!(self, "no field matches {query:?}");
+        }
+        let hits: Vec<_> = hits.iter().map(|hit| (hit.offset, hit.field)).collect();
+        self.print_hits(&hits);
+    }
+
+    /// Prints search or `find` hits laid out as `--group-by` asks.
+    fn print_hits(&self, hits: &[(usize, &FieldEntry)]) {
+        if let GroupBy::Offset = self.options.group_by {
+            for (offset, field) in hits {
+                say!(
+                    self,
+                    "{} {}",
+                    self.theme.hint.paint(&format!("0x{offset:x}")),
+                    self.format_field(field)
+                );
             }
-            Err(_) => {
-                println!("invalid offset");
+            return;
+        }
+        // Classes are listed in order of their first hit.
+        let mut classes: Vec<Vec<(usize, &FieldEntry)>> = Vec::new();
+        for &(offset, field) in hits {
+            let same_class = |other: &FieldEntry| {
+                (&other.class_name, &other.type_scope_name)
+                    == (&field.class_name, &field.type_scope_name)
+            };
+            match classes
+                .iter_mut()
+                .find(|class_hits| same_class(class_hits[0].1))
+            {
+                Some(class_hits) => class_hits.push((offset, field)),
+                None => classes.push(vec![(offset, field)]),
+            }
+        }
+        for class_hits in classes {
+            let first = class_hits[0].1;
+            say!(
+                self,
+                "{}{}",
+                self.qualified_name(&first.type_scope_name, &first.class_name),
+                self.scope_note(&first.type_scope_name)
+            );
+            for (offset, field) in class_hits {
+                say!(
+                    self,
+                    "  {} {} {}",
+                    self.theme.hint.paint(&format!("0x{offset:x}")),
+                    self.theme.type_.paint(&field.type_),
+                    field.name
+                );
             }
         }
     }
-    Ok(())
+
+    /// Resolves `count` distinct populated offsets picked at random, one by default.
+    fn print_random_offsets(&self, count: &str) {
+        let count = if count.is_empty() {
+            Ok(1)
+        } else {
+            count.parse()
+        };
+        let Ok(count) = count else {
+            say!(self, "invalid count");
+            return;
+        };
+        // `choose_multiple` allocates room for `count` up front, so don't ask for more offsets
+        // than there are.
+        let count = count.min(self.offset_to_fields.len());
+        let offsets = self
+            .offset_to_fields
+            .keys()
+            .choose_multiple(&mut rand::thread_rng(), count);
+        for offset in offsets {
+            if let Format::Text | Format::Table = self.options.format {
+                say!(
+                    self,
+                    "{}",
+                    self.theme.hint.paint(&format!("0x{:x}", offset))
+                );
+            }
+            self.print_offset(*offset);
+        }
+    }
+
+    /// Prints the field layout of every class whose name matches the wildcard `pattern`.
+    fn print_classes(&self, pattern: &str) {
+        let mut classes: Vec<_> = self
+            .sdk
+            .type_scopes
+            .iter()
+            .flat_map(|(scope_name, type_scope)| {
+                type_scope
+                    .classes
+                    .iter()
+                    .map(move |(class_name, class)| (class_name, scope_name, class))
+            })
+            .filter(|(class_name, _, _)| glob_match(pattern, class_name))
+            .collect();
+        classes.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        if classes.is_empty() {
+            say!(self, "no class matches {pattern:?}");
+        }
+        for (class_name, scope_name, class) in classes {
+            let mut fields: Vec<_> = class.fields.iter().collect();
+            fields.sort_by(|a, b| (a.1.offset, a.0).cmp(&(b.1.offset, b.0)));
+            if let Format::Json = self.options.format {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, field)| {
+                        json!({
+                            "offset": field.offset,
+                            "name": name,
+                            "type": field.type_,
+                            "refers_to": self.referenced_class(&field.type_),
+                        })
+                    })
+                    .collect();
+                say!(
+                    self,
+                    "{}",
+                    json!({ "class": class_name, "scope": scope_name, "fields": fields })
+                );
+                continue;
+            }
+            say!(
+                self,
+                "{}{}",
+                self.qualified_name(scope_name, class_name),
+                self.scope_note(scope_name)
+            );
+            for (name, field) in fields {
+                let refers_to = match self.referenced_class(&field.type_) {
+                    Some(class_name) => format!(" -> {}", self.theme.class.paint(class_name)),
+                    None => String::new(),
+                };
+                say!(
+                    self,
+                    "  {} {} {name}{refers_to}",
+                    self.theme.hint.paint(&format!("0x{:x}", field.offset)),
+                    self.theme.type_.paint(&field.type_),
+                );
+            }
+        }
+    }
+
+    /// The loaded class a field of type `type_` leads into, when that isn't just the type itself.
+    fn referenced_class<'a>(&'a self, type_: &'a str) -> Option<&'a str> {
+        let class_name = self.typemap.resolve(type_);
+        (class_name != type_ && self.sdk.has_class(class_name)).then_some(class_name)
+    }
+
+    /// Lists fields whose type refers to a class, directly or through a handle, pointer or type
+    /// map entry, that isn't declared in any loaded scope, grouped by the declaring class.
+    fn print_dangling_types(&self) {
+        let mut dangling: Vec<_> = self
+            .sdk
+            .type_scopes
+            .iter()
+            .flat_map(|(type_scope_name, type_scope)| {
+                type_scope
+                    .classes
+                    .iter()
+                    .flat_map(move |(class_name, class)| {
+                        class.fields.iter().map(move |(field_name, field)| {
+                            (type_scope_name, class_name, field_name, &field.type_)
+                        })
+                    })
+            })
+            .filter_map(|(type_scope_name, class_name, field_name, type_)| {
+                let referenced = self.typemap.resolve(type_);
+                (looks_like_class(referenced) && !self.sdk.has_class(referenced)).then_some((
+                    type_scope_name,
+                    class_name,
+                    field_name,
+                    type_,
+                    referenced,
+                ))
+            })
+            .collect();
+        dangling.sort();
+        if dangling.is_empty() {
+            say!(self, "every referenced class is loaded");
+            return;
+        }
+        let mut current = None;
+        for (type_scope_name, class_name, field_name, type_, referenced) in &dangling {
+            if current != Some((type_scope_name, class_name)) {
+                current = Some((type_scope_name, class_name));
+                say!(
+                    self,
+                    "{}{}",
+                    self.qualified_name(type_scope_name, class_name),
+                    self.scope_note(type_scope_name)
+                );
+            }
+            say!(
+                self,
+                "  {field_name}: {} {}",
+                self.theme.type_.paint(type_),
+                self.theme.hint.paint(&format!("-> missing {referenced}"))
+            );
+        }
+        say!(self, "{} dangling references", dangling.len());
+    }
+
+    /// Prints one row per scope with the fields that scope declares at the offset.
+    fn compare_scopes(&self, input: &str) {
+        let Some(offset) = self.resolve_offset(input) else {
+            say!(self, "invalid offset");
+            return;
+        };
+        let fields = self
+            .offset_to_fields
+            .get(&offset)
+            .map_or(&[][..], Vec::as_slice);
+        let mut scopes: Vec<_> = self.sdk.type_scopes.keys().collect();
+        scopes.sort();
+        let in_scope = |scope: &str| -> Vec<&FieldEntry> {
+            fields
+                .iter()
+                .filter(|field| field.type_scope_name == scope && self.is_shown(field))
+                .collect()
+        };
+        if let Format::Json = self.options.format {
+            let scopes: serde_json::Map<_, _> = scopes
+                .iter()
+                .map(|scope| (scope.to_string(), json!(in_scope(scope))))
+                .collect();
+            say!(self, "{}", json!({ "offset": offset, "scopes": scopes }));
+            return;
+        }
+        let width = scopes.iter().map(|scope| scope.len()).max().unwrap_or(0);
+        for scope in scopes {
+            let cells: Vec<_> = in_scope(scope)
+                .into_iter()
+                .map(|field| {
+                    format!(
+                        "{} {}{}{}",
+                        self.theme.type_.paint(&field.type_),
+                        self.qualified_name(&field.type_scope_name, &field.class_name),
+                        self.theme.separator.paint("::"),
+                        field.name
+                    )
+                })
+                .collect();
+            let row = format!(
+                "{} {}",
+                self.theme.scope.paint(&format!("{scope:<width$}")),
+                cells.join("; ")
+            );
+            say!(self, "{}", row.trim_end());
+        }
+    }
+
+    /// Narrates each step taken to resolve an offset.
+    fn explain(&self, input: &str) {
+        let Some((mut offset, resolution)) = self.resolve_offset_explained(input) else {
+            match self.anchor {
+                None if input.trim_start().starts_with('@') => {
+                    say!(
+                        self,
+                        "{input:?} is relative to the anchor, but no anchor is set"
+                    )
+                }
+                Some(_) => say!(
+                    self,
+                    "{input:?} is neither an alias, a hex offset nor @+N/@-N from the anchor"
+                ),
+                None => say!(self, "{input:?} is neither an alias nor a hex offset"),
+            }
+            return;
+        };
+        say!(self, "{resolution}");
+        let masked = self.masked(offset);
+        if masked != offset {
+            say!(
+                self,
+                "mask {} bits rounds 0x{offset:x} down to 0x{masked:x}",
+                self.mask_bits
+            );
+            offset = masked;
+        }
+        match self.offset_to_fields.get(&offset) {
+            Some(fields) => {
+                let mut classes: Vec<_> = fields
+                    .iter()
+                    .map(|field| (&field.type_scope_name, &field.class_name))
+                    .collect();
+                classes.sort();
+                classes.dedup();
+                let mut scopes: Vec<_> = classes.iter().map(|(scope, _)| *scope).collect();
+                scopes.sort();
+                scopes.dedup();
+                say!(
+                    self,
+                    "offset 0x{offset:x} found in {} classes across {} scopes",
+                    classes.len(),
+                    scopes.len()
+                );
+                for field in fields {
+                    let hidden = if self.is_shown(field) {
+                        ""
+                    } else {
+                        ", hidden from lookups by --only"
+                    };
+                    let class_name = self.qualified_name(&field.type_scope_name, &field.class_name);
+                    let scope = if self.options.qualified {
+                        String::new()
+                    } else {
+                        format!(" in {}", self.theme.scope.paint(&field.type_scope_name))
+                    };
+                    say!(
+                        self,
+                        "  {class_name}::{}{scope} has type {:?} as recorded in the schema{hidden}",
+                        field.name,
+                        field.type_
+                    );
+                }
+                let names = count_name_groups(fields);
+                if names > 1 {
+                    say!(
+                        self,
+                        "fields were grouped by name into {names} groups; different names at one \
+                         offset usually belong to unrelated classes"
+                    );
+                }
+            }
+            None => say!(self, "no field is declared at offset 0x{offset:x}"),
+        }
+        let below = self.offset_to_fields.keys().filter(|&&o| o < offset).max();
+        match below {
+            Some(below) => {
+                let fields = &self.offset_to_fields[below];
+                say!(
+                    self,
+                    "nearest field below is at 0x{below:x} (-0x{:x}): {}",
+                    offset - below,
+                    fields
+                        .iter()
+                        .map(|field| format!("{}::{}", field.class_name, field.name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            None => say!(self, "no field is declared below 0x{offset:x}"),
+        }
+    }
+
+    /// `count <offset>` prints how many fields a lookup of the offset would list.
+    fn print_field_count(&self, input: &str) {
+        match self.resolve_offset(input) {
+            Some(offset) => say!(
+                self,
+                "{}",
+                self.offset_to_fields
+                    .get(&self.masked(offset))
+                    .map_or(0, |fields| {
+                        fields.iter().filter(|field| self.is_shown(field)).count()
+                    })
+            ),
+            None => say!(self, "invalid offset"),
+        }
+    }
+
+    /// Lists every distinct field type with the number of fields using it, most common first.
+    fn print_types(&self) {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for field in self.offset_to_fields.values().flatten() {
+            *counts.entry(&field.type_).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        if let Format::Json = self.options.format {
+            let counts: Vec<_> = counts
+                .iter()
+                .map(|(type_, count)| json!({ "type": type_, "count": count }))
+                .collect();
+            say!(self, "{}", json!(counts));
+            return;
+        }
+        for (type_, count) in counts {
+            say!(self, "{count:>6} {}", self.theme.type_.paint(type_));
+        }
+    }
+
+    /// Prints the bare type of `Class::field`, or `unknown`, so the output can be captured by scripts.
+    fn print_field_type(&self, path: &str) {
+        let type_ = path.split_once("::").and_then(|(class_name, field_name)| {
+            self.sdk
+                .get_field_type(class_name.trim(), field_name.trim())
+        });
+        say!(self, "{}", type_.unwrap_or("unknown"));
+    }
+
+    /// Lists classes that declare more than one field at the same offset.
+    fn print_shared_offsets(&self) {
+        if self.shared_offsets.is_empty() {
+            say!(self, "no class declares two fields at the same offset");
+        }
+        for shared in &self.shared_offsets {
+            say!(
+                self,
+                "{}{}{{{}}} share offset 0x{:x}{}",
+                self.qualified_name(&shared.type_scope_name, &shared.class_name),
+                self.theme.separator.paint("::"),
+                shared.names.join(", "),
+                shared.offset,
+                self.scope_note(&shared.type_scope_name),
+            );
+        }
+    }
+
+    /// Prints which schema was loaded and the effective output settings.
+    fn print_banner(&self, source: &str, theme: ThemeName) {
+        let classes: usize = self
+            .sdk
+            .type_scopes
+            .values()
+            .map(|type_scope| type_scope.classes.len())
+            .sum();
+        let fields: usize = self.offset_to_fields.values().map(Vec::len).sum();
+        let color = if colored::control::SHOULD_COLORIZE.should_colorize() {
+            "on"
+        } else {
+            "off"
+        };
+        let theme = theme.to_possible_value().expect("no skipped themes");
+        let format = self
+            .options
+            .format
+            .to_possible_value()
+            .expect("no skipped formats");
+        let lines = [
+            format!("schema: {source}"),
+            format!(
+                "loaded {} scopes, {classes} classes, {fields} fields at {} offsets",
+                self.sdk.type_scopes.len(),
+                self.offset_to_fields.len(),
+            ),
+            format!(
+                "theme: {}, color: {color}, format: {}",
+                theme.get_name(),
+                format.get_name()
+            ),
+        ];
+        for line in lines {
+            say!(self, "{}", self.theme.hint.paint(&line));
+        }
+        if !self.shared_offsets.is_empty() {
+            say!(
+                self,
+                "warning: {} offsets are shared by several fields of the same class:",
+                self.shared_offsets.len()
+            );
+            self.print_shared_offsets();
+        }
+    }
+
+    /// Renders the prompt template, substituting `{hint}` and `{state}`.
+    fn prompt(&self) -> String {
+        let mut state = Vec::new();
+        if !self.options.only.is_empty() {
+            state.push(format!("only {}", self.options.only.join(",")));
+        }
+        if self.mask_bits > 0 {
+            state.push(format!("mask {} bits", self.mask_bits));
+        }
+        if let Some(base) = self.base {
+            state.push(format!("base 0x{base:x}"));
+        }
+        if let Some(anchor) = self.anchor {
+            state.push(format!("anchor 0x{anchor:x}"));
+        }
+        let state = if state.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", state.join(", "))
+        };
+        self.options
+            .prompt
+            .replace("{hint}", &self.theme.hint.paint("(hex)").to_string())
+            .replace("{state}", &self.theme.hint.paint(&state).to_string())
+    }
+
+    /// Reads commands from `commands` until `exit` or end of input, appending each to `record`
+    /// if given.
+    fn run_interactive_loop(
+        &mut self,
+        commands: &mut dyn BufRead,
+        mut record: Option<File>,
+    ) -> Result<()> {
+        let mut input = String::new();
+        loop {
+            print!("{}", self.prompt());
+            stdout().flush()?;
+            input.clear();
+            if commands.read_line(&mut input)? == 0 {
+                // Ctrl+D leaves the cursor after the prompt.
+                say!(self);
+                break;
+            }
+            let input = input.trim();
+            if input.is_empty() || input == "exit" {
+                break;
+            }
+            if let Some(record) = &mut record {
+                writeln!(record, "{input}").context("failed to record command")?;
+            }
+            self.run_command(input);
+        }
+        if !self.options.quiet {
+            let Stats {
+                queries,
+                hits,
+                misses,
+                offsets,
+            } = &self.stats;
+            say!(
+                self,
+                "{}",
+                self.theme.hint.paint(&format!(
+                    "{queries} queries, {hits} hits, {misses} misses, {} unique offsets",
+                    offsets.len()
+                ))
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Expands `${VAR}` references in `path` from the runtime environment.
+fn expand_env_vars(path: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            bail!("unterminated `${{` in schema path {path:?}");
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let value = std::env::var(name)
+            .map_err(|_| anyhow!("environment variable `{name}` used in schema path is not set"))?;
+        expanded.push_str(&value);
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
 }
 
 fn main() -> Result<()> {
-    let path = dotenv!("SCHEMA_JSON");
-    let sdk: Sdk = serde_json::from_str(&std::fs::read_to_string(path)?)?;
-    let offset_to_fields = make_offset_to_fields(&sdk);
-    run_interactive_loop(&offset_to_fields)?;
+    dotenv::dotenv().ok();
+    let args = Args::parse();
+    let contains = args.contains.is_some();
+    let result = run(args);
+    if let (Err(e), true) = (&result, contains) {
+        // `--contains` exits with 1 when the field is missing, so errors need a code of their own.
+        eprintln!("Error: {e:?}");
+        std::process::exit(2);
+    }
+    result
+}
+
+fn run(args: Args) -> Result<()> {
+    let theme = Theme::builtin(args.theme).with_env_overrides()?;
+    let mut source = match args.schema {
+        Some(schema) => schema,
+        None => expand_env_vars(dotenv!("SCHEMA_JSON"))?,
+    };
+    if args.check {
+        let failures = check_schema_files(&source, &args.load)?;
+        std::process::exit(failures.min(255) as i32);
+    }
+    let schema_source = SchemaSource {
+        location: source.clone(),
+        load: args.load,
+        patch: args.patch,
+        merge_fields: args.merge_fields,
+        quiet: args.options.quiet,
+    };
+    let (sdk, schema_source) = if args.stdin {
+        source = "stdin".to_string();
+        let mut text = String::new();
+        stdin()
+            .read_to_string(&mut text)
+            .context("schema on stdin is not valid UTF-8")?;
+        (schema_source.finish(Sdk::from_json(&text, "stdin")?)?, None)
+    } else {
+        (schema_source.load()?, Some(schema_source))
+    };
+    if args.fail_on_conflict {
+        let offset_to_fields = make_offset_to_fields(&sdk);
+        let conflicts = find_type_conflicts(&offset_to_fields);
+        for conflict in &conflicts {
+            let types: Vec<_> = conflict
+                .fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{} ({}!{})",
+                        field.type_, field.type_scope_name, field.class_name
+                    )
+                })
+                .collect();
+            eprintln!(
+                "conflict at 0x{:x}: {} is {}",
+                conflict.offset,
+                conflict.name,
+                types.join(", ")
+            );
+        }
+        if !conflicts.is_empty() {
+            bail!("{} type conflicts in the offset index", conflicts.len());
+        }
+    }
+    if let Some(path) = &args.expect_file {
+        let failures = expect::check_expectations(&sdk, path)?;
+        std::process::exit(failures.min(255) as i32);
+    }
+    if let Some(path) = &args.unresolved_report {
+        return export::write_unresolved_report(&sdk, path);
+    }
+    if let Some(path) = &args.dump_index {
+        // Export what lookups would show, so a dump can stand in for an interactive session.
+        let mut offset_to_fields = build_index(&sdk, &args.options.scope_order);
+        for fields in offset_to_fields.values_mut() {
+            fields.retain(|field| args.options.shows(field));
+        }
+        offset_to_fields.retain(|_, fields| !fields.is_empty());
+        return export::dump_index(&offset_to_fields, path);
+    }
+    let aliases = Aliases::load(args.aliases)?;
+    let typemap = match &args.typemap {
+        Some(path) => TypeMap::load(path)?,
+        None => TypeMap::default(),
+    };
+    let mut session = Session::new(sdk, schema_source, theme, aliases, typemap, args.options);
+    if let Some(offset) = args.contains {
+        let found = session
+            .offset_to_fields
+            .get(&offset)
+            .is_some_and(|fields| fields.iter().any(|field| session.is_shown(field)));
+        std::process::exit(if found { 0 } else { 1 });
+    }
+    if args.tui {
+        return tui::run(&mut session);
+    }
+    if let Some(path) = &args.replay {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read recording {}", path.display()))?;
+        for command in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if !session.options.quiet {
+                println!("{}", session.theme.hint.paint(&format!("> {command}")));
+            }
+            session.run_command(command);
+        }
+    } else if args.queries.is_empty() {
+        // With `--stdin` the schema used up standard input, so the prompt reads the terminal.
+        let mut commands: Box<dyn BufRead> = if args.stdin {
+            let tty = File::open("/dev/tty")
+                .context("--stdin without queries needs a terminal to read commands from")?;
+            Box::new(BufReader::new(tty))
+        } else {
+            Box::new(stdin().lock())
+        };
+        let record = match &args.record {
+            Some(path) => Some(
+                File::options()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("failed to open {}", path.display()))?,
+            ),
+            None => None,
+        };
+        if !session.options.quiet {
+            session.print_banner(&source, args.theme);
+        }
+        session.run_interactive_loop(&mut commands, record)?;
+    }
+    for query in &args.queries {
+        session.run_command(query);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_env_vars_substitutes_set_variables() {
+        std::env::set_var("SDK_LOOKUP_TEST_ROOT", "/games/cs2");
+        assert_eq!(
+            expand_env_vars("${SDK_LOOKUP_TEST_ROOT}/schema").unwrap(),
+            "/games/cs2/schema"
+        );
+        assert_eq!(
+            expand_env_vars("${SDK_LOOKUP_TEST_ROOT}${SDK_LOOKUP_TEST_ROOT}").unwrap(),
+            "/games/cs2/games/cs2"
+        );
+        assert_eq!(expand_env_vars("plain/path").unwrap(), "plain/path");
+    }
+
+    #[test]
+    fn expand_env_vars_rejects_unset_and_unterminated_references() {
+        assert!(expand_env_vars("${SDK_LOOKUP_TEST_UNSET}/schema").is_err());
+        assert!(expand_env_vars("${SDK_LOOKUP_TEST_ROOT/schema").is_err());
+    }
+
+    fn session() -> Session {
+        let args = Args::parse_from(["sdk-lookup"]);
+        let aliases = Aliases::load(PathBuf::from("/nonexistent/sdk-lookup-aliases")).unwrap();
+        Session::new(
+            Sdk::from_json("{}", "test").unwrap(),
+            None,
+            Theme::builtin(ThemeName::Default),
+            aliases,
+            TypeMap::default(),
+            args.options,
+        )
+    }
+
+    #[test]
+    fn resolve_offset_keeps_plus_absolute_with_an_anchor() {
+        let mut session = session();
+        assert_eq!(session.resolve_offset("+0x1a4"), Some(0x1a4));
+        assert_eq!(session.resolve_offset("@+8"), None);
+        session.anchor = Some(0x1a0);
+        assert_eq!(session.resolve_offset("+0x1a4"), Some(0x1a4));
+        assert_eq!(session.resolve_offset("@+8"), Some(0x1a8));
+        assert_eq!(session.resolve_offset("@ -0x10"), Some(0x190));
+        assert_eq!(session.resolve_offset("@-0x1a8"), None);
+        assert_eq!(session.resolve_offset("@8"), None);
+    }
+
+    #[test]
+    fn capture_collects_command_output() {
+        let mut session = session();
+        let output = session.capture(|session| {
+            session.run_command("anchor 1a0");
+            session.run_command("anchor");
+        });
+        assert_eq!(output, "anchor: 0x1a0\n");
+        assert_eq!(session.anchor, Some(0x1a0));
+        assert!(session.captured.borrow().is_none());
+    }
+
+    #[test]
+    fn parse_offset_accepts_pasted_forms() {
+        let cases = [
+            ("1a4", Some(0x1a4)),
+            ("0x1a4", Some(0x1a4)),
+            ("0X1A4", Some(0x1a4)),
+            ("+0x1A4", Some(0x1a4)),
+            ("0x0001a4", Some(0x1a4)),
+            (" 0x1a4 ", Some(0x1a4)),
+            ("", None),
+            ("+", None),
+            ("0x", None),
+            ("-0x1a4", None),
+            ("0x1a4g", None),
+            ("0x 1a4", None),
+            ("m_iHealth", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_offset(input), expected, "{input:?}");
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::FieldEntry;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::collections::HashMap;
+
+pub struct SearchHit<'a> {
+    pub score: i64,
+    pub offset: usize,
+    pub field: &'a FieldEntry,
+}
+
+/// Fuzzy matches `query` against the name of every field `shown` accepts, best matches first.
+/// Unless `literal` is set, names are also matched with their `m_` prefix stripped and the better
+/// score is kept.
+pub fn fuzzy_search<'a>(
+    offset_to_fields: &'a HashMap<usize, Vec<FieldEntry>>,
+    query: &str,
+    limit: usize,
+    literal: bool,
+    shown: impl Fn(&FieldEntry) -> bool,
+) -> Vec<SearchHit<'a>> {
+    let matcher = SkimMatcherV2::default();
+    let mut hits: Vec<_> = offset_to_fields
+        .iter()
+        .flat_map(|(offset, fields)| fields.iter().map(move |field| (*offset, field)))
+        .filter(|(_, field)| shown(field))
+        .filter_map(|(offset, field)| {
+            let raw = matcher.fuzzy_match(&field.name, query);
+            let stripped = match strip_prefix(&field.name) {
+                Some(stripped) if !literal => matcher.fuzzy_match(stripped, query),
+                _ => None,
+            };
+            let score = raw.max(stripped)?;
+            Some(SearchHit {
+                score,
+                offset,
+                field,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.field.name.cmp(&b.field.name))
+            .then_with(|| a.offset.cmp(&b.offset))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// Strips the `m_` member prefix and, when it is followed by a capital, a single Hungarian type
+/// character, so `m_iHealth` becomes `Health` and `m_health` becomes `health`.
+fn strip_prefix(name: &str) -> Option<&str> {
+    let rest = name.strip_prefix("m_")?;
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some(kind), Some(next)) if kind.is_ascii_lowercase() && next.is_ascii_uppercase() => {
+            Some(&rest[1..])
+        }
+        _ => Some(rest),
+    }
+}
+
+/// Matches `text` against a shell-style pattern where `*` matches any run of characters and `?`
+/// matches exactly one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Position to resume from after the most recent `*`: (pattern index, text index).
+    let mut backtrack = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn glob_match_table() {
+        let cases = [
+            ("m_iHealth", "m_iHealth", true),
+            ("m_i*", "m_iHealth", true),
+            ("*Health", "m_iHealth", true),
+            ("m_?Health", "m_iHealth", true),
+            ("m_*H*h", "m_iHealth", true),
+            ("*", "", true),
+            ("", "", true),
+            ("?", "", false),
+            ("m_i*", "m_flSpeed", false),
+            ("m_iHealth", "m_iHealthMax", false),
+            ("*Max", "m_iHealth", false),
+        ];
+        for (pattern, text, expected) in cases {
+            assert_eq!(
+                glob_match(pattern, text),
+                expected,
+                "{pattern:?} vs {text:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::FieldEntry;
+
+/// A parsed `--template` line. Placeholders are checked once when the option is parsed, so a
+/// typo is reported at startup instead of being printed literally on every lookup.
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Offset(OffsetSpec),
+    Class,
+    Name,
+    Type,
+    Scope,
+    Bit,
+}
+
+/// The `[[fill]align][#][0][width][radix]` spec of an `{offset:...}` placeholder, following
+/// Rust's format spec. Without a radix the offset keeps the default `0x1a4` notation.
+#[derive(Debug, Clone)]
+struct OffsetSpec {
+    fill: char,
+    align: char,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    radix: Option<char>,
+}
+
+impl Template {
+    /// Parses a template, rejecting unknown placeholders and malformed offset specs. `{{` and
+    /// `}}` stand for literal braces.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err(format!("unterminated placeholder {{{rest}"));
+                    };
+                    let placeholder = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_placeholder(placeholder)?);
+                }
+                '}' => return Err("unmatched `}`, write `}}` for a literal brace".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template { segments })
+    }
+
+    /// Fills the template in for one field at `offset`.
+    pub fn render(&self, offset: usize, field: &FieldEntry) -> String {
+        let mut line = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => line.push_str(text),
+                Segment::Offset(spec) => line.push_str(&spec.format(offset)),
+                Segment::Class => line.push_str(&field.class_name),
+                Segment::Name => line.push_str(&field.name),
+                Segment::Type => line.push_str(&field.type_),
+                Segment::Scope => line.push_str(&field.type_scope_name),
+                Segment::Bit => {
+                    if let Some(bit) = field.bit_offset {
+                        line.push_str(&bit.to_string());
+                    }
+                }
+            }
+        }
+        line
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name, Some(spec)),
+        None => (placeholder, None),
+    };
+    let segment = match name {
+        "offset" => return Ok(Segment::Offset(OffsetSpec::parse(spec.unwrap_or(""))?)),
+        "class" => Segment::Class,
+        "name" => Segment::Name,
+        "type" => Segment::Type,
+        "scope" => Segment::Scope,
+        "bit" => Segment::Bit,
+        _ => return Err(format!("unknown placeholder {{{placeholder}}}")),
+    };
+    match spec {
+        Some(_) => Err(format!(
+            "only {{offset}} takes a format spec, not {{{name}}}"
+        )),
+        None => Ok(segment),
+    }
+}
+
+impl OffsetSpec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid offset spec {{offset:{spec}}}");
+        let mut rest = spec;
+        let (mut fill, mut align) = (' ', '>');
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(f), Some(a @ ('<' | '>' | '^'))) => {
+                (fill, align) = (f, a);
+                rest = chars.as_str();
+            }
+            (Some(a @ ('<' | '>' | '^')), _) => {
+                align = a;
+                rest = &rest[1..];
+            }
+            _ => {}
+        }
+        let alternate = rest.starts_with('#');
+        rest = rest.strip_prefix('#').unwrap_or(rest);
+        let zero = rest.starts_with('0');
+        rest = rest.strip_prefix('0').unwrap_or(rest);
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let width = match digits {
+            0 => 0,
+            _ => rest[..digits].parse().map_err(|_| invalid())?,
+        };
+        rest = &rest[digits..];
+        let radix = match rest {
+            "" => None,
+            "x" | "X" | "d" | "o" | "b" => rest.chars().next(),
+            _ => return Err(invalid()),
+        };
+        Ok(OffsetSpec {
+            fill,
+            align,
+            alternate,
+            zero,
+            width,
+            radix,
+        })
+    }
+
+    fn format(&self, offset: usize) -> String {
+        let (prefix, digits) = match self.radix {
+            None => ("0x", format!("{offset:x}")),
+            Some('X') => ("0x", format!("{offset:X}")),
+            Some('d') => ("", offset.to_string()),
+            Some('o') => ("0o", format!("{offset:o}")),
+            Some('b') => ("0b", format!("{offset:b}")),
+            _ => ("0x", format!("{offset:x}")),
+        };
+        let prefix = if self.alternate || self.radix.is_none() {
+            prefix
+        } else {
+            ""
+        };
+        let len = prefix.len() + digits.len();
+        let padding = self.width.saturating_sub(len);
+        if self.zero {
+            // As in Rust, zero padding goes between the prefix and the digits and ignores the
+            // fill and alignment.
+            return format!("{prefix}{}{digits}", "0".repeat(padding));
+        }
+        let (before, after) = match self.align {
+            '<' => (0, padding),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (padding, 0),
+        };
+        let fill = |count| self.fill.to_string().repeat(count);
+        format!("{}{prefix}{digits}{}", fill(before), fill(after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use crate::FieldEntry;
+
+    fn field() -> FieldEntry {
+        FieldEntry {
+            name: "m_iHealth".to_string(),
+            type_: "int32".to_string(),
+            class_name: "C_BaseEntity".to_string(),
+            type_scope_name: "client".to_string(),
+            bit_offset: None,
+        }
+    }
+
+    #[test]
+    fn render_table() {
+        let cases = [
+            ("{offset}", "0x1a4"),
+            ("{offset:x}", "1a4"),
+            ("{offset:X}", "1A4"),
+            ("{offset:#x}", "0x1a4"),
+            ("{offset:#X}", "0x1A4"),
+            ("{offset:d}", "420"),
+            ("{offset:o}", "644"),
+            ("{offset:#b}", "0b110100100"),
+            ("{offset:04x}", "01a4"),
+            ("{offset:08x}", "000001a4"),
+            ("{offset:#010x}", "0x000001a4"),
+            ("{offset:6}", " 0x1a4"),
+            ("{offset:<6d}|", "420   |"),
+            ("{offset:*^7x}", "**1a4**"),
+            ("{offset:2x}", "1a4"),
+            (
+                "{scope}!{class}::{name} : {type}",
+                "client!C_BaseEntity::m_iHealth : int32",
+            ),
+            ("{{name}} {name}", "{name} m_iHealth"),
+            ("{name}{bit}", "m_iHealth"),
+            ("plain", "plain"),
+        ];
+        for (template, expected) in cases {
+            let rendered = Template::parse(template).unwrap().render(0x1a4, &field());
+            assert_eq!(rendered, expected, "{template:?}");
+        }
+    }
+
+    #[test]
+    fn render_bit() {
+        let field = FieldEntry {
+            bit_offset: Some(5),
+            ..field()
+        };
+        let template = Template::parse("{offset}:bit{bit} {name}").unwrap();
+        assert_eq!(template.render(0x1a4, &field), "0x1a4:bit5 m_iHealth");
+    }
+
+    #[test]
+    fn parse_rejects_malformed_templates() {
+        for template in [
+            "{offst}",
+            "{offset:q}",
+            "{offset:08xx}",
+            "{name:>10}",
+            "{offset",
+            "offset}",
+        ] {
+            assert!(Template::parse(template).is_err(), "{template:?}");
+        }
+    }
+}
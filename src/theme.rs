@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use std::str::FromStr;
+
+/// How a single output element is styled.
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Plain,
+    Dimmed,
+    Color(Color),
+}
+
+impl Style {
+    pub fn paint(self, text: &str) -> ColoredString {
+        match self {
+            Style::Plain => text.normal(),
+            Style::Dimmed => text.dimmed(),
+            Style::Color(color) => text.color(color),
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "plain" | "none" => Ok(Style::Plain),
+            "dimmed" => Ok(Style::Dimmed),
+            _ => Color::from_str(s)
+                .map(Style::Color)
+                .map_err(|_| anyhow!("unknown color {s:?}")),
+        }
+    }
+}
+
+const OKABE_ITO_BLUE: Color = Color::TrueColor {
+    r: 0,
+    g: 114,
+    b: 178,
+};
+const OKABE_ITO_ORANGE: Color = Color::TrueColor {
+    r: 230,
+    g: 159,
+    b: 0,
+};
+const OKABE_ITO_SKY_BLUE: Color = Color::TrueColor {
+    r: 86,
+    g: 180,
+    b: 233,
+};
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ThemeName {
+    #[default]
+    Default,
+    /// Blue/orange palette that stays distinguishable with common color vision deficiencies
+    Colorblind,
+    /// No colors at all
+    Plain,
+}
+
+/// Styles for every colored element of the output.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub type_: Style,
+    pub class: Style,
+    pub separator: Style,
+    pub scope: Style,
+    pub hint: Style,
+}
+
+impl Theme {
+    pub fn builtin(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme {
+                type_: Style::Color(Color::Magenta),
+                class: Style::Color(Color::Yellow),
+                separator: Style::Dimmed,
+                scope: Style::Dimmed,
+                hint: Style::Dimmed,
+            },
+            ThemeName::Colorblind => Theme {
+                type_: Style::Color(OKABE_ITO_BLUE),
+                class: Style::Color(OKABE_ITO_ORANGE),
+                separator: Style::Dimmed,
+                scope: Style::Color(OKABE_ITO_SKY_BLUE),
+                hint: Style::Dimmed,
+            },
+            ThemeName::Plain => Theme {
+                type_: Style::Plain,
+                class: Style::Plain,
+                separator: Style::Plain,
+                scope: Style::Plain,
+                hint: Style::Plain,
+            },
+        }
+    }
+
+    /// Replaces element styles with those set in `COLOR_TYPE`, `COLOR_CLASS`, `COLOR_SEPARATOR`,
+    /// `COLOR_SCOPE` and `COLOR_HINT`, if present.
+    pub fn with_env_overrides(mut self) -> Result<Self> {
+        for (var, style) in [
+            ("COLOR_TYPE", &mut self.type_),
+            ("COLOR_CLASS", &mut self.class),
+            ("COLOR_SEPARATOR", &mut self.separator),
+            ("COLOR_SCOPE", &mut self.scope),
+            ("COLOR_HINT", &mut self.hint),
+        ] {
+            if let Ok(value) = std::env::var(var) {
+                *style = value.parse().map_err(|e| anyhow!("{var}: {e}"))?;
+            }
+        }
+        Ok(self)
+    }
+}
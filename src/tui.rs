@@ -0,0 +1,200 @@
+use crate::{Session, COMMANDS};
+use anyhow::{Context, Result};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+/// Runs the full-screen frontend: lookups and searches render live as they are typed, and Enter
+/// runs the input as a prompt command, showing what it printed.
+pub fn run(session: &mut Session) -> Result<()> {
+    // Raw mode may already be on when entering the alternate screen is what failed.
+    let mut terminal = ratatui::try_init()
+        .inspect_err(|_| ratatui::restore())
+        .context("failed to start the full-screen interface")?;
+    let result = event_loop(&mut terminal, session);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, session: &mut Session) -> Result<()> {
+    let mut input = String::new();
+    // Output of the last command run with Enter, shown while the input isn't a lookup.
+    let mut output = String::new();
+    let mut scroll: u16 = 0;
+    loop {
+        let results = match preview(session, &input) {
+            Some(preview) => ansi_lines(&preview),
+            None => ansi_lines(&output),
+        };
+        scroll = scroll.min(results.len().saturating_sub(1) as u16);
+        terminal.draw(|frame| draw(frame, session, &input, results, scroll))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => break,
+            KeyCode::Char('c' | 'd') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Enter if !input.trim().is_empty() => {
+                let command = std::mem::take(&mut input);
+                output = session.capture(|session| session.run_command(command.trim()));
+                scroll = 0;
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                scroll = 0;
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                scroll = 0;
+            }
+            KeyCode::Up => scroll = scroll.saturating_sub(1),
+            KeyCode::Down => scroll = scroll.saturating_add(1),
+            KeyCode::PageUp => scroll = scroll.saturating_sub(10),
+            KeyCode::PageDown => scroll = scroll.saturating_add(10),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Renders what a lookup or `/` search of `input` would print, without counting it in the
+/// session stats. Returns `None` for commands and unresolvable input, which only run on Enter.
+fn preview(session: &mut Session, input: &str) -> Option<String> {
+    let input = input.trim();
+    if let Some(query) = input.strip_prefix('/') {
+        return Some(session.capture(|session| session.print_search(query.trim())));
+    }
+    let offset = match input.strip_prefix(':') {
+        Some(input) => input.trim(),
+        None => {
+            let command = input.split_once(' ').map_or(input, |(command, _)| command);
+            if COMMANDS.iter().any(|(name, _)| *name == command) || input.starts_with("offsetof(") {
+                return None;
+            }
+            input
+        }
+    };
+    let offset = session.masked(session.resolve_offset(offset)?);
+    Some(session.capture(|session| session.print_offset(offset)))
+}
+
+fn draw(frame: &mut Frame, session: &Session, input: &str, results: Vec<Line>, scroll: u16) {
+    let [main, input_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(frame.area());
+    let [results_area, sidebar_area] =
+        Layout::horizontal([Constraint::Min(1), Constraint::Length(32)]).areas(main);
+    frame.render_widget(
+        Paragraph::new(results)
+            .scroll((scroll, 0))
+            .block(Block::bordered().title("results")),
+        results_area,
+    );
+    frame.render_widget(
+        Paragraph::new(sidebar(session)).block(Block::bordered().title("state")),
+        sidebar_area,
+    );
+    frame.render_widget(
+        Paragraph::new(input).block(
+            Block::bordered().title("offset, alias, /search or command; enter runs, esc quits"),
+        ),
+        input_area,
+    );
+    let cursor = Span::raw(input).width() as u16;
+    frame.set_cursor_position((input_area.x + 1 + cursor, input_area.y + 1));
+}
+
+fn sidebar(session: &Session) -> Vec<Line<'static>> {
+    let classes: usize = session
+        .sdk
+        .type_scopes
+        .values()
+        .map(|type_scope| type_scope.classes.len())
+        .sum();
+    let mut lines = vec![
+        Line::from(format!("scopes: {}", session.sdk.type_scopes.len())),
+        Line::from(format!("classes: {classes}")),
+        Line::from(format!("offsets: {}", session.offset_to_fields.len())),
+        Line::from(format!("aliases: {}", session.aliases.iter().count())),
+        Line::from(match session.mask_bits {
+            0 => "mask: off".to_string(),
+            bits => format!("mask: {bits} bits"),
+        }),
+    ];
+    if let Some(base) = session.base {
+        lines.push(Line::from(format!("base: 0x{base:x}")));
+    }
+    if let Some(anchor) = session.anchor {
+        lines.push(Line::from(format!("anchor: 0x{anchor:x}")));
+    }
+    if !session.options.only.is_empty() {
+        lines.push(Line::from(format!(
+            "only: {}",
+            session.options.only.join(",")
+        )));
+    }
+    lines
+}
+
+/// Turns captured command output back into styled lines, interpreting the SGR escape codes the
+/// theme colors were printed with.
+fn ansi_lines(text: &str) -> Vec<Line<'static>> {
+    let mut style = Style::default();
+    text.lines()
+        .map(|line| {
+            let mut spans = Vec::new();
+            let mut rest = line;
+            while let Some(start) = rest.find("\x1b[") {
+                if start > 0 {
+                    spans.push(Span::styled(rest[..start].to_string(), style));
+                }
+                let escape = &rest[start + 2..];
+                let Some(end) = escape.find('m') else {
+                    rest = "";
+                    break;
+                };
+                style = apply_sgr(style, &escape[..end]);
+                rest = &escape[end + 1..];
+            }
+            if !rest.is_empty() {
+                spans.push(Span::styled(rest.to_string(), style));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            39 => style.fg(Color::Reset),
+            38 => match codes.next() {
+                Some(5) => style.fg(Color::Indexed(codes.next().unwrap_or(0))),
+                Some(2) => {
+                    let mut channel = || codes.next().unwrap_or(0);
+                    style.fg(Color::Rgb(channel(), channel(), channel()))
+                }
+                _ => style,
+            },
+            _ => style,
+        };
+    }
+    style
+}
@@ -0,0 +1,57 @@
+use anyhow::{bail, Context, Result};
+use std::{collections::HashMap, path::Path};
+
+/// Resolves schema type strings to the name of the class they refer to.
+#[derive(Default)]
+pub struct TypeMap {
+    classes: HashMap<String, String>,
+}
+
+impl TypeMap {
+    /// Loads `type = Class` lines from `path`; blank lines and lines starting with `#` are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read type map {}", path.display()))?;
+        let mut classes = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((type_, class_name)) = line.rsplit_once('=') else {
+                bail!("{}:{}: expected `type = Class`", path.display(), i + 1);
+            };
+            classes.insert(type_.trim().to_string(), class_name.trim().to_string());
+        }
+        Ok(TypeMap { classes })
+    }
+
+    /// Returns the class `type_` refers to: its mapped class if the type map has an entry,
+    /// otherwise the type with pointer and `CHandle< >` wrappers removed.
+    pub fn resolve<'a>(&'a self, type_: &'a str) -> &'a str {
+        let type_ = type_.trim();
+        if let Some(class_name) = self.classes.get(type_) {
+            return class_name;
+        }
+        let unwrapped = type_.trim_end_matches('*').trim_end();
+        let unwrapped = unwrapped
+            .strip_prefix("CHandle<")
+            .and_then(|inner| inner.strip_suffix('>'))
+            .unwrap_or(unwrapped)
+            .trim();
+        match self.classes.get(unwrapped) {
+            Some(class_name) => class_name,
+            None => unwrapped,
+        }
+    }
+}
+
+/// Whether `name` follows the schema's class naming, like `C_BaseEntity` or `CBodyComponent`.
+pub fn looks_like_class(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('C')
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}